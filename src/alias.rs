@@ -0,0 +1,76 @@
+﻿//! §3.3
+
+use crate::{Properties, Property, Str, StructureError};
+
+/// `/aliases` 节点中的一个别名。
+pub struct Alias<'a> {
+    /// 别名。
+    pub name: Str<'a>,
+    /// 别名指向的节点的绝对路径。
+    pub path: Str<'a>,
+    /// 别名去掉数字后缀的部分，例如 `serial0` 的 `serial`。
+    pub stem: Str<'a>,
+    /// 别名的数字后缀，例如 `serial0` 的 `0`。
+    pub id: Option<u32>,
+}
+
+/// 别名迭代器。
+pub struct Aliases<'a> {
+    props: Option<Properties<'a>>,
+    /// 查找 `/aliases` 节点时发现的错误。
+    pending: Option<StructureError>,
+}
+
+impl<'a> Aliases<'a> {
+    #[inline]
+    pub(crate) fn new(props: Result<Option<Properties<'a>>, StructureError>) -> Self {
+        match props {
+            Ok(props) => Self {
+                props,
+                pending: None,
+            },
+            Err(e) => Self {
+                props: None,
+                pending: Some(e),
+            },
+        }
+    }
+}
+
+impl<'a> Iterator for Aliases<'a> {
+    type Item = Result<Alias<'a>, StructureError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.pending.take() {
+            return Some(Err(e));
+        }
+        for prop in self.props.as_mut()? {
+            let (name, value) = match prop {
+                Ok(Property::General { name, value }) => (name, value),
+                Ok(_) => continue,
+                Err(e) => return Some(Err(e)),
+            };
+            // 别名的值是以 '\0' 结尾的绝对路径
+            match value.as_bytes().split_last() {
+                Some((b'\0', path)) if path.starts_with(b"/") && name.as_bytes() != b"name" => {
+                    let bytes = name.0;
+                    let len = bytes.len()
+                        - bytes
+                            .iter()
+                            .rev()
+                            .take_while(|c| c.is_ascii_digit())
+                            .count();
+                    let (stem, id) = bytes.split_at(len);
+                    return Some(Ok(Alias {
+                        name,
+                        path: Str(path),
+                        stem: Str(stem),
+                        id: core::str::from_utf8(id).ok().and_then(|id| id.parse().ok()),
+                    }));
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+}
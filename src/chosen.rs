@@ -0,0 +1,75 @@
+﻿//! §3.6
+
+use crate::{Node, Reg, RegCfg, Str, StructureError};
+
+/// `/chosen` 节点。
+#[derive(Default)]
+pub struct Chosen<'a> {
+    /// `bootargs`，内核命令行。
+    pub bootargs: Option<Str<'a>>,
+    /// `stdout-path` 中 `:` 之前的部分，是标准输出设备的路径或别名。
+    pub stdout_path: Option<Str<'a>>,
+    /// `stdout-path` 中 `:` 之后的部分，是标准输出设备的选项，例如 `115200n8`。
+    pub stdout_options: Option<Str<'a>>,
+    /// `linux,initrd-start`，initrd 的起始物理地址。
+    pub initrd_start: Option<u64>,
+    /// `linux,initrd-end`，initrd 的结束物理地址。
+    pub initrd_end: Option<u64>,
+    /// `rng-seed`，用于初始化随机数生成器的熵。
+    pub rng_seed: Option<&'a [u8]>,
+    /// `kaslr-seed`，内核地址随机化的种子。
+    pub kaslr_seed: Option<u64>,
+    /// `linux,usable-memory-range`，按根节点的 `#address-cells` 和 `#size-cells` 解析。
+    pub usable_memory_range: Option<Reg<'a>>,
+    /// `linux,elfcorehdr`，按根节点的 `#address-cells` 和 `#size-cells` 解析。
+    pub elfcorehdr: Option<Reg<'a>>,
+}
+
+impl<'a> Chosen<'a> {
+    /// 从 `/chosen` 节点解析。不合规范的属性被忽略。
+    pub(crate) fn new(node: &Node<'a>) -> Result<Self, StructureError> {
+        let mut ans = Self::default();
+        let cells = node.parent_cells();
+        for prop in node.raw_properties() {
+            let prop = prop?;
+            match prop.name {
+                b"bootargs" => ans.bootargs = prop.value().as_str(),
+                b"stdout-path" => {
+                    if let Some(path) = prop.value().as_str() {
+                        match path.0.iter().position(|c| *c == b':') {
+                            Some(i) => {
+                                ans.stdout_path = Some(Str(&path.0[..i]));
+                                ans.stdout_options = Some(Str(&path.0[i + 1..]));
+                            }
+                            None => ans.stdout_path = Some(path),
+                        }
+                    }
+                }
+                b"linux,initrd-start" => ans.initrd_start = prop.value().as_u64(),
+                b"linux,initrd-end" => ans.initrd_end = prop.value().as_u64(),
+                b"rng-seed" => ans.rng_seed = Some(prop.value().as_bytes()),
+                b"kaslr-seed" => ans.kaslr_seed = prop.value().as_u64(),
+                b"linux,usable-memory-range" | b"linux,elfcorehdr"
+                    if cells.reg_size() != 0
+                        && !prop.buf.is_empty()
+                        && prop.buf.len().is_multiple_of(cells.reg_size()) =>
+                {
+                    let reg = Some(Reg {
+                        buf: prop.buf,
+                        cfg: RegCfg {
+                            address_cells: cells.address,
+                            size_cells: cells.size,
+                        },
+                    });
+                    if prop.name == b"linux,elfcorehdr" {
+                        ans.elfcorehdr = reg;
+                    } else {
+                        ans.usable_memory_range = reg;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(ans)
+    }
+}
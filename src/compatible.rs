@@ -0,0 +1,84 @@
+﻿use crate::{Events, Node, Property, StructureError};
+
+/// 按兼容性查找节点的迭代器。
+pub struct FindCompatible<'a, 'b> {
+    events: Events<'a>,
+    compatible: &'b [&'b str],
+    available: bool,
+}
+
+/// 兼容性匹配的节点。
+pub struct CompatibleMatch<'a> {
+    /// 匹配的节点。
+    pub node: Node<'a>,
+    /// 第一个匹配的项在节点 `compatible` 属性中的序号。越小表示匹配越精确。
+    pub index: usize,
+    /// 这一项在查询的兼容性列表中的序号。
+    pub query: usize,
+}
+
+impl<'a, 'b> FindCompatible<'a, 'b> {
+    #[inline]
+    pub(crate) fn new(events: Events<'a>, compatible: &'b [&'b str]) -> Self {
+        Self {
+            events,
+            compatible,
+            available: false,
+        }
+    }
+
+    /// 跳过 `status` 不可用的节点及其子树。
+    #[inline]
+    pub fn available(mut self) -> Self {
+        self.available = true;
+        self
+    }
+
+    /// 检查节点是否匹配。
+    fn check(&self, node: Node<'a>) -> Result<Option<CompatibleMatch<'a>>, StructureError> {
+        let mut best = None;
+        let mut available = true;
+        for prop in node.properties() {
+            match prop? {
+                Property::Compatible(list) => {
+                    best = list.enumerate().find_map(|(index, s)| {
+                        self.compatible
+                            .iter()
+                            .position(|c| s.as_bytes() == c.as_bytes())
+                            .map(|query| (index, query))
+                    });
+                }
+                Property::Status(status) => available = status.is_available(),
+                _ => {}
+            }
+        }
+        Ok(match best {
+            Some((index, query)) if available || !self.available => {
+                Some(CompatibleMatch { node, index, query })
+            }
+            _ => None,
+        })
+    }
+}
+
+impl<'a> Iterator for FindCompatible<'a, '_> {
+    type Item = Result<CompatibleMatch<'a>, StructureError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let node = self.events.next_node()?.and_then(|node| {
+                // 不可用的节点的子节点也不可用
+                if self.available && !node.status()?.is_available() {
+                    self.events.skip_subtree();
+                    return Ok(None);
+                }
+                self.check(node)
+            });
+            match node {
+                Ok(Some(m)) => return Some(Ok(m)),
+                Ok(None) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
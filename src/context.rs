@@ -1,4 +1,4 @@
-﻿use crate::{tree_on_stack::Node, Str};
+﻿use crate::{tree_on_stack::Node, PHandle, Property, Ranges, RawCells, Str, StructureBlock};
 use core::fmt;
 
 /// 遍历上下文。
@@ -6,14 +6,25 @@ pub struct Context<'a>(Node<'a, Inner<'a>>);
 
 struct Inner<'a> {
     name: Str<'a>,
+    /// 父节点的 cells。
     cells: Cells,
+    /// 父节点作为总线的地址映射。
+    bus: Bus<'a>,
+    /// 节点自身的中断父节点。
+    interrupt_parent: Option<InterruptParent>,
 }
 
 impl Context<'_> {
-    pub(crate) const ROOT: Self = Context(Node::root(Inner {
-        name: Str(b""),
-        cells: Cells::DEFAULT,
-    }));
+    /// 根节点的上下文。根节点也可以有 `interrupt-parent`。
+    #[inline]
+    pub(crate) const fn root(interrupt_parent: Option<InterruptParent>) -> Self {
+        Context(Node::root(Inner {
+            name: Str(b""),
+            cells: Cells::DEFAULT,
+            bus: Bus::new(false, None),
+            interrupt_parent,
+        }))
+    }
 
     /// 返回路径层数。定义根节点的子节点层数为 0。
     #[inline]
@@ -29,7 +40,7 @@ impl Context<'_> {
 
     /// 返回路径最后一级的节点名。
     #[inline]
-    pub fn name(&self) -> Str {
+    pub fn name(&self) -> Str<'_> {
         self.0.as_ref().name
     }
 
@@ -38,6 +49,91 @@ impl Context<'_> {
         self.0.as_ref().cells
     }
 
+    /// 将当前节点 `reg` 中的地址转换为 CPU 物理地址。
+    ///
+    /// 地址逐级经过祖先节点的 `ranges` 转换，直到根节点。
+    /// 每一级都比较完整的地址，包括 PCI 地址的地址空间编码，见 [`RangesEntry::translate`]。
+    /// 如果某个祖先没有 `ranges` 或地址不在其映射中，地址不可转换，分别返回
+    /// [`TranslateError::NoRanges`] 和 [`TranslateError::OutOfRange`]。
+    pub fn translate(&self, address: RawCells) -> Result<u64, TranslateError> {
+        let mut address = (address.high(), address.low_u64());
+        let mut node = &self.0;
+        while let Some(parent) = node.parent {
+            if parent.is_root() {
+                break;
+            }
+            let level = parent.level();
+            let (high, low) = address;
+            address = node
+                .data
+                .bus
+                .ranges
+                .as_ref()
+                .ok_or(TranslateError::NoRanges { level })?
+                .map(false, high, low)
+                .ok_or(TranslateError::OutOfRange { level })?;
+            node = parent;
+        }
+        Ok(address.1)
+    }
+
+    /// 将当前节点发出的 DMA 地址转换为 CPU 物理地址。
+    ///
+    /// 地址逐级经过祖先节点的 `dma-ranges` 转换，直到根节点。DMA 地址只是一个数值，不比较地址空间。
+    /// 没有 `dma-ranges` 的祖先视作恒等映射。如果地址不在某个映射中，返回 `None`。
+    pub fn dma_to_cpu(&self, address: u64) -> Option<u64> {
+        let mut address = (RawCells(&[]), address);
+        let mut node = &self.0;
+        while let Some(parent) = node.parent {
+            if parent.is_root() {
+                break;
+            }
+            if let Some(ranges) = &node.data.bus.dma_ranges {
+                let (high, low) = address;
+                address = ranges.map(false, high, low)?;
+            }
+            node = parent;
+        }
+        Some(address.1)
+    }
+
+    /// 将 CPU 物理地址转换为当前节点可见的 DMA 地址。
+    ///
+    /// 是 [`Context::dma_to_cpu`] 的逆变换。
+    pub fn cpu_to_dma(&self, address: u64) -> Option<u64> {
+        self.0
+            .fold((true, RawCells(&[]), address), |(top, high, low), inner| {
+                // 根节点的 `dma-ranges` 不构成转换
+                match &inner.bus.dma_ranges {
+                    Some(ranges) if !top => ranges
+                        .map(true, high, low)
+                        .map(|(high, low)| (false, high, low)),
+                    _ => Some((false, high, low)),
+                }
+                .ok_or(())
+            })
+            .ok()
+            .map(|(_, _, address)| address)
+    }
+
+    /// 如果最近的有 `dma-coherent` 或 `dma-noncoherent` 属性的祖先节点是 DMA 连贯的则返回 `true`。
+    ///
+    /// 不包括当前节点自身的属性，见 [`Node::is_dma_coherent`](crate::Node::is_dma_coherent)。
+    #[inline]
+    pub fn is_dma_coherent(&self) -> bool {
+        self.0.as_ref().bus.dma_coherent
+    }
+
+    #[inline]
+    pub(crate) fn bus(&self) -> &Bus<'_> {
+        &self.0.as_ref().bus
+    }
+
+    #[inline]
+    pub(crate) fn interrupt_parent(&self) -> Option<InterruptParent> {
+        self.0.as_ref().interrupt_parent
+    }
+
     /// 将路径字符串格式化到 `buf` 中。
     ///
     /// 如果返回 `Ok(n)`，表示字符串长度为 `n`（`n` 不大于 `buf.len()`）。
@@ -63,8 +159,19 @@ impl Context<'_> {
 
 impl<'a> Context<'a> {
     #[inline]
-    pub(crate) fn grow(&'a self, name: Str<'a>, cells: Cells) -> Self {
-        Self(self.0.grow(Inner { name, cells }))
+    pub(crate) fn grow(
+        &'a self,
+        name: Str<'a>,
+        cells: Cells,
+        bus: Bus<'a>,
+        interrupt_parent: Option<InterruptParent>,
+    ) -> Self {
+        Self(self.0.grow(Inner {
+            name,
+            cells,
+            bus,
+            interrupt_parent,
+        }))
     }
 }
 
@@ -77,22 +184,106 @@ impl fmt::Display for Context<'_> {
     }
 }
 
+/// 地址转换可能发现的错误类型。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TranslateError {
+    /// 祖先节点没有 `ranges`，其子总线地址不能映射到父总线。
+    NoRanges {
+        /// 祖先节点的路径层数，见 [`Context::level`]。
+        level: usize,
+    },
+    /// 地址不在祖先节点 `ranges` 的任何映射中。
+    OutOfRange {
+        /// 祖先节点的路径层数，见 [`Context::level`]。
+        level: usize,
+    },
+}
+
+/// 节点作为总线的地址映射。
+#[derive(Clone)]
+pub(crate) struct Bus<'a> {
+    /// `ranges`。
+    pub ranges: Option<Ranges<'a>>,
+    /// `dma-ranges`。
+    pub dma_ranges: Option<Ranges<'a>>,
+    /// 节点或其祖先有 `dma-coherent` 属性，且没有被更近的 `dma-noncoherent` 覆盖。
+    pub dma_coherent: bool,
+    /// 没有 `interrupt-parent` 的子节点的中断父节点。
+    ///
+    /// 节点有 `#interrupt-cells` 时是节点自身，否则与节点自身的中断父节点相同。
+    pub interrupt_parent: Option<InterruptParent>,
+}
+
+impl<'a> Bus<'a> {
+    /// 构造一个继承 `dma_coherent` 和中断父节点的空映射。
+    #[inline]
+    pub const fn new(dma_coherent: bool, interrupt_parent: Option<InterruptParent>) -> Self {
+        Self {
+            ranges: None,
+            dma_ranges: None,
+            dma_coherent,
+            interrupt_parent,
+        }
+    }
+
+    /// 记录地址映射相关的属性。
+    #[inline]
+    pub fn absorb(&mut self, prop: &Property<'a>) {
+        match prop {
+            Property::Ranges(ranges) => self.ranges = Some(ranges.clone()),
+            Property::DmaRanges(ranges) => self.dma_ranges = Some(ranges.clone()),
+            Property::DmaCoherent => self.dma_coherent = true,
+            Property::DmaNonCoherent => self.dma_coherent = false,
+            Property::InterruptCells(cells) => {
+                self.interrupt_parent = Some(InterruptParent {
+                    phandle: None,
+                    cells: *cells,
+                })
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 中断父节点。
+#[derive(Clone, Copy)]
+pub(crate) struct InterruptParent {
+    /// 通过 `interrupt-parent` 找到时的引用号，用于避免重复查找。
+    pub phandle: Option<PHandle>,
+    /// 中断父节点的 `#interrupt-cells`。
+    pub cells: u32,
+}
+
 #[derive(Clone, Copy)]
 pub(crate) struct Cells {
     pub address: u32,
     pub size: u32,
-    pub interrupt: u32,
+    pub interrupt: Option<u32>,
 }
 
 impl Cells {
     pub const DEFAULT: Self = Self {
         address: 2,
         size: 1,
-        interrupt: 1,
+        interrupt: None,
     };
 
+    /// 如果属性是一个 cell 的 `#address-cells`、`#size-cells` 或 `#interrupt-cells`，更新对应的值。
+    pub fn absorb(&mut self, name: &[u8], value: &[StructureBlock], len: usize) {
+        let ([value], 4) = (value, len) else {
+            return;
+        };
+        let value = value.into_u32();
+        match name {
+            b"#address-cells" => self.address = value,
+            b"#size-cells" => self.size = value,
+            b"#interrupt-cells" => self.interrupt = Some(value),
+            _ => {}
+        }
+    }
+
     #[inline]
     pub fn reg_size(&self) -> usize {
-        (self.address + self.size) as _
+        (self.address as usize).saturating_add(self.size as _)
     }
 }
@@ -0,0 +1,226 @@
+﻿use crate::{
+    context::{Cells, InterruptParent},
+    walker::{parse_prop, Token, Walker},
+    Node, Property, Str, StructureError,
+};
+
+const MAX_DEPTH: usize = 32;
+
+/// 一个打开的节点。
+#[derive(Clone, Copy)]
+struct Level {
+    /// 节点自身的 cells。
+    cells: Cells,
+    /// 节点自身的中断父节点。
+    interrupt_parent: Option<InterruptParent>,
+    /// 子节点按引用号找到的中断父节点。
+    resolved: Option<InterruptParent>,
+}
+
+impl Level {
+    const ROOT: Self = Self {
+        cells: Cells::DEFAULT,
+        interrupt_parent: None,
+        resolved: None,
+    };
+
+    /// 没有 `interrupt-parent` 的子节点的中断父节点。
+    #[inline]
+    fn child_interrupt_parent(&self) -> Option<InterruptParent> {
+        match self.cells.interrupt {
+            Some(cells) => Some(InterruptParent {
+                phandle: None,
+                cells,
+            }),
+            None => self.interrupt_parent,
+        }
+    }
+}
+
+/// 设备树遍历事件。
+pub enum Event<'a> {
+    /// 进入节点。
+    BeginNode {
+        /// 节点名。
+        name: Str<'a>,
+        /// 节点深度。定义根节点深度为 0。
+        depth: usize,
+    },
+    /// 当前节点的属性。
+    Property(Property<'a>),
+    /// 离开当前节点。
+    EndNode,
+}
+
+/// 深度优先的设备树遍历事件迭代器。
+///
+/// 遇到错误后迭代器不再产生任何事件。
+pub struct Events<'a> {
+    walker: Walker<'a>,
+    /// 所有打开的节点。
+    stack: [Level; MAX_DEPTH],
+    /// 打开节点的数量。
+    depth: usize,
+    /// 已经进入过根节点。
+    rooted: bool,
+    /// 最后一次进入的节点的开始标记偏移。
+    begin: usize,
+    /// 构造时发现的错误。
+    pending: Option<StructureError>,
+    done: bool,
+}
+
+impl<'a> Events<'a> {
+    /// 支持的最大节点深度。
+    pub const MAX_DEPTH: usize = MAX_DEPTH;
+
+    #[inline]
+    pub(crate) fn new(walker: Result<Walker<'a>, StructureError>) -> Self {
+        let (walker, pending) = match walker {
+            Ok(walker) => (walker, None),
+            Err(e) => (Walker::new(&[], &[], false), Some(e)),
+        };
+        Self {
+            walker,
+            stack: [Level::ROOT; Self::MAX_DEPTH],
+            depth: 0,
+            rooted: false,
+            begin: 0,
+            pending,
+            done: false,
+        }
+    }
+
+    /// 跳过当前节点剩余的属性和子树而不解析，下一个事件将是当前节点的 [`Event::EndNode`]。
+    ///
+    /// 如果没有打开的节点，什么也不做。
+    pub fn skip_subtree(&mut self) {
+        if self.done || self.depth == 0 {
+            return;
+        }
+        let mut level = 0usize;
+        loop {
+            let walker = self.walker.clone();
+            match self.walker.next_token() {
+                Ok(Token::Begin(_)) => level += 1,
+                Ok(Token::End) if level == 0 => {
+                    // 留给 `next` 产生
+                    self.walker = walker;
+                    return;
+                }
+                Ok(Token::End) => level -= 1,
+                Ok(Token::Prop { .. }) => {}
+                // 留给 `next` 报告错误
+                Ok(Token::Finish) | Err(_) => {
+                    self.walker = walker;
+                    return;
+                }
+            }
+        }
+    }
+
+    /// 前进到下一个节点的开始，返回此节点。
+    pub(crate) fn next_node(&mut self) -> Option<Result<Node<'a>, StructureError>> {
+        loop {
+            match self.next()? {
+                Ok(Event::BeginNode { name, depth }) => {
+                    let parent = match depth {
+                        0 => Cells::DEFAULT,
+                        n => self.stack[n - 1].cells,
+                    };
+                    return Some(Ok(Node::new(self.walker.clone(), name, self.begin, parent)));
+                }
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+
+    fn next_event(&mut self) -> Result<Option<Event<'a>>, StructureError> {
+        let offset = self.walker.offset();
+        match self.walker.next_token()? {
+            Token::Begin(name) => {
+                // 只能有一个根节点
+                if self.depth == 0 && self.rooted {
+                    return Err(StructureError::Unbalanced { offset });
+                }
+                self.rooted = true;
+                if self.depth == Self::MAX_DEPTH {
+                    return Err(StructureError::TooDeep { offset });
+                }
+                let interrupt_parent = match self.depth.checked_sub(1) {
+                    Some(parent) => {
+                        let parent = &mut self.stack[parent];
+                        let inherited = parent.child_interrupt_parent();
+                        self.walker
+                            .interrupt_parent(inherited, &mut parent.resolved)?
+                    }
+                    None => self.walker.interrupt_parent(None, &mut None)?,
+                };
+                self.stack[self.depth] = Level {
+                    interrupt_parent,
+                    ..Level::ROOT
+                };
+                self.depth += 1;
+                self.begin = offset;
+                Ok(Some(Event::BeginNode {
+                    name,
+                    depth: self.depth - 1,
+                }))
+            }
+            Token::End => match self.depth.checked_sub(1) {
+                Some(depth) => {
+                    self.depth = depth;
+                    Ok(Some(Event::EndNode))
+                }
+                None => Err(StructureError::Unbalanced { offset }),
+            },
+            Token::Prop { name, value, len } => {
+                let parent = match self.depth {
+                    0 => return Err(StructureError::Unbalanced { offset }),
+                    1 => Cells::DEFAULT,
+                    n => self.stack[n - 2].cells,
+                };
+                let level = &mut self.stack[self.depth - 1];
+                let interrupt_cells = level.interrupt_parent.map(|p| p.cells);
+                let prop = parse_prop(
+                    name,
+                    value,
+                    len,
+                    parent,
+                    &mut level.cells,
+                    interrupt_cells,
+                    &self.walker,
+                );
+                Ok(Some(Event::Property(prop)))
+            }
+            Token::Finish if self.depth == 0 && self.rooted => Ok(None),
+            Token::Finish => Err(StructureError::Unbalanced { offset }),
+        }
+    }
+}
+
+impl<'a> Iterator for Events<'a> {
+    type Item = Result<Event<'a>, StructureError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        if let Some(e) = self.pending.take() {
+            self.done = true;
+            return Some(Err(e));
+        }
+        match self.next_event() {
+            Ok(Some(event)) => Some(Ok(event)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
@@ -1,18 +1,117 @@
 ﻿use crate::{is_aligned, U32BigEndian};
 use core::ops::Range;
 
-pub(crate) struct FdtHeader {
-    magic: U32BigEndian,
-    pub totalsize: U32BigEndian,
-    pub off_dt_struct: U32BigEndian,
-    pub off_dt_strings: U32BigEndian,
-    pub off_mem_rsvmap: U32BigEndian,
-    pub version: U32BigEndian,
-    pub last_comp_version: U32BigEndian,
-    #[allow(unused)]
-    pub boot_cpuid_phys: U32BigEndian,
-    pub size_dt_strings: U32BigEndian,
-    pub size_dt_struct: U32BigEndian,
+/// §5.2 设备树首部。
+#[repr(C)]
+#[derive(Debug)]
+pub struct FdtHeader {
+    pub(crate) magic: U32BigEndian,
+    pub(crate) totalsize: U32BigEndian,
+    pub(crate) off_dt_struct: U32BigEndian,
+    pub(crate) off_dt_strings: U32BigEndian,
+    pub(crate) off_mem_rsvmap: U32BigEndian,
+    pub(crate) version: U32BigEndian,
+    pub(crate) last_comp_version: U32BigEndian,
+    pub(crate) boot_cpuid_phys: U32BigEndian,
+    pub(crate) size_dt_strings: U32BigEndian,
+    pub(crate) size_dt_struct: U32BigEndian,
+}
+
+impl FdtHeader {
+    /// 魔数，应该是 0xd00dfeed。
+    #[inline]
+    pub const fn magic(&self) -> u32 {
+        self.magic.into_u32()
+    }
+
+    /// 设备树的总字节数。
+    #[inline]
+    pub const fn totalsize(&self) -> u32 {
+        self.totalsize.into_u32()
+    }
+
+    /// 结构块相对首部的字节偏移。
+    #[inline]
+    pub const fn off_dt_struct(&self) -> u32 {
+        self.off_dt_struct.into_u32()
+    }
+
+    /// 字符串区相对首部的字节偏移。
+    #[inline]
+    pub const fn off_dt_strings(&self) -> u32 {
+        self.off_dt_strings.into_u32()
+    }
+
+    /// 地址保护区相对首部的字节偏移。
+    #[inline]
+    pub const fn off_mem_rsvmap(&self) -> u32 {
+        self.off_mem_rsvmap.into_u32()
+    }
+
+    /// 设备树的版本。
+    #[inline]
+    pub const fn version(&self) -> u32 {
+        self.version.into_u32()
+    }
+
+    /// 设备树向后兼容的最低版本。
+    #[inline]
+    pub const fn last_comp_version(&self) -> u32 {
+        self.last_comp_version.into_u32()
+    }
+
+    /// 启动核的物理 id，即其 cpu 节点 `reg` 属性的值。
+    ///
+    /// v2 之前的首部没有这个字段。
+    #[inline]
+    pub const fn boot_cpuid_phys(&self) -> Option<u32> {
+        if self.version() >= 2 {
+            Some(self.boot_cpuid_phys.into_u32())
+        } else {
+            None
+        }
+    }
+
+    /// 字符串区的字节数。
+    ///
+    /// v3 之前的首部没有这个字段。
+    #[inline]
+    pub const fn size_dt_strings(&self) -> Option<u32> {
+        if self.version() >= 3 {
+            Some(self.size_dt_strings.into_u32())
+        } else {
+            None
+        }
+    }
+
+    /// 结构块的字节数。
+    ///
+    /// v17 之前的首部没有这个字段。
+    #[inline]
+    pub const fn size_dt_struct(&self) -> Option<u32> {
+        if self.version() >= 17 {
+            Some(self.size_dt_struct.into_u32())
+        } else {
+            None
+        }
+    }
+
+    /// 首部的字节数，取决于版本。
+    #[inline]
+    pub const fn header_len(&self) -> u32 {
+        match self.version() {
+            0 | 1 => 28,
+            2 => 32,
+            3..=16 => 36,
+            _ => 40,
+        }
+    }
+
+    /// v16 之前的结构块格式：节点名是完整路径，长度不小于 8 的属性值按 8 字节对齐。
+    #[inline]
+    pub(crate) const fn is_legacy(&self) -> bool {
+        self.version() < 16
+    }
 }
 
 /// 首部检查可能发现的错误类型。
@@ -79,11 +178,9 @@ const STRUCT_SIZE_ALIGN_BITS: usize = FOUR;
 
 const MAGIC: U32BigEndian = U32BigEndian::from_u32(0xd00dfeed);
 const VERSION: u32 = 17;
-const LAST_COMP_VERSION: u32 = 16;
-const LEN_HEADER: u32 = core::mem::size_of::<FdtHeader>() as _;
 
 impl FdtHeader {
-    pub fn verify(&self, filter: impl Fn(&HeaderError) -> bool) -> Result<(), HeaderError> {
+    pub(crate) fn verify(&self, filter: impl Fn(&HeaderError) -> bool) -> Result<(), HeaderError> {
         use HeaderError as E;
         // 检查整体对齐
         if !is_aligned(self as *const _ as _, DTB_ALIGN_BITS) {
@@ -93,21 +190,21 @@ impl FdtHeader {
         if self.magic != MAGIC {
             check(&filter, E::Magic(self.magic.into_u32()))?;
         }
-        if self.version.into_u32() < VERSION {
-            check(&filter, E::Version(self.version.into_u32()))?;
+        let version = self.version();
+        if version == 0 {
+            check(&filter, E::Version(version))?;
         }
-        if self.last_comp_version.into_u32() != LAST_COMP_VERSION {
-            check(
-                &filter,
-                E::LastCompVersion(self.last_comp_version.into_u32()),
-            )?;
+        let last_comp_version = self.last_comp_version();
+        if last_comp_version > VERSION || last_comp_version > version {
+            check(&filter, E::LastCompVersion(last_comp_version))?;
         }
         // 检查结构
         let len_total = self.totalsize.into_u32();
-        if len_total < LEN_HEADER {
+        let len_header = self.header_len();
+        if len_total < len_header {
             check(&filter, E::TotalSize(len_total))?;
         }
-        let mut range = LEN_HEADER..len_total;
+        let mut range = len_header..len_total;
         // 保留内存块
         let off_memrev = self.off_mem_rsvmap.into_u32();
         if !is_aligned(off_memrev as _, MEMREV_ALIGN_BITS) {
@@ -137,32 +234,54 @@ impl FdtHeader {
                 },
             )?;
         }
-        let len_struct = self.size_dt_struct.into_u32();
-        if !is_aligned(len_struct as _, STRUCT_SIZE_ALIGN_BITS) {
-            check(&filter, E::StructMisaligned(misaligned(len_struct)))?;
-        }
-        if len_struct > range.len() as u32 {
-            check(
-                &filter,
-                E::StructSize {
-                    value: len_struct,
-                    max: range.len() as _,
-                },
-            )?;
-        }
-        unsafe {
+        // v17 之前没有结构块大小，结构块可能延伸到设备树结尾
+        let len_struct = match self.size_dt_struct() {
+            Some(len_struct) => {
+                if !is_aligned(len_struct as _, STRUCT_SIZE_ALIGN_BITS) {
+                    check(&filter, E::StructMisaligned(misaligned(len_struct)))?;
+                }
+                if len_struct > range.len() as u32 {
+                    check(
+                        &filter,
+                        E::StructSize {
+                            value: len_struct,
+                            max: range.len() as _,
+                        },
+                    )?;
+                }
+                len_struct
+            }
+            None => len_total.saturating_sub(off_struct),
+        };
+        // 结构块越界或不对齐时无法检查内容
+        let end_struct = off_struct.saturating_add(len_struct);
+        if !range.contains(&off_struct)
+            || end_struct > len_total
+            || !is_aligned(off_struct as _, STRUCT_ALIGN_BITS)
+        {
+            check(&filter, E::StructContent)?;
+        } else {
             use crate::StructureBlock as Blk;
-            match core::slice::from_raw_parts(
-                (self as *const _ as *const u8)
-                    .offset(off_struct as _)
-                    .cast::<Blk>(),
-                len_struct as usize / Blk::LEN,
-            ) {
+            match unsafe {
+                core::slice::from_raw_parts(
+                    (self as *const _ as *const u8)
+                        .offset(off_struct as _)
+                        .cast::<Blk>(),
+                    len_struct as usize / Blk::LEN,
+                )
+            } {
                 [Blk::NODE_BEGIN, Blk::EMPTY_STR, .., Blk::END] => {}
+                // 不知道结构块在哪里结束
+                [Blk::NODE_BEGIN, Blk::EMPTY_STR, ..] if version < 17 => {}
+                // 根节点名是 "/"
+                [Blk::NODE_BEGIN, ..] if self.is_legacy() => {}
                 _ => check(&filter, E::StructContent)?,
             }
         }
-        range = off_struct + len_struct..len_total;
+        range = match self.size_dt_struct() {
+            Some(_) => end_struct..len_total,
+            None => off_struct..len_total,
+        };
         // 字符串块
         let off_strings = self.off_dt_strings.into_u32();
         if !range.contains(&off_strings) {
@@ -174,15 +293,16 @@ impl FdtHeader {
                 },
             )?;
         }
-        let len_strings = self.size_dt_strings.into_u32();
-        if len_strings > range.len() as u32 {
-            check(
-                filter,
-                E::StringsSize {
-                    value: len_strings,
-                    max: range.len() as _,
-                },
-            )?;
+        if let Some(len_strings) = self.size_dt_strings() {
+            if len_strings > range.len() as u32 {
+                check(
+                    filter,
+                    E::StringsSize {
+                        value: len_strings,
+                        max: range.len() as _,
+                    },
+                )?;
+            }
         }
         Ok(())
     }
@@ -0,0 +1,254 @@
+﻿//! §2.4
+
+use crate::{
+    Interrupts, Node, PHandle, PHandleArgs, RawCells, StructureBlock as Blk, StructureError,
+};
+
+/// 查找中断父节点时最多经过的节点数，防止 `interrupt-parent` 成环。
+const MAX_HOPS: usize = 64;
+
+impl<'a> Node<'a> {
+    /// 返回节点的中断父节点，即中断说明符的解释者。
+    ///
+    /// 中断父节点来自 `interrupt-parent` 属性，缺省为设备树中的父节点。
+    /// 如果这样找到的节点没有 `#interrupt-cells`，从它继续寻找。
+    /// 找不到或 `interrupt-parent` 成环时返回 `None`。
+    pub fn interrupt_parent(&self) -> Result<Option<Self>, StructureError> {
+        let mut node = self.clone();
+        for _ in 0..MAX_HOPS {
+            let next = match node.raw_phandle(b"interrupt-parent")? {
+                Some(phandle) => node.find_phandle(phandle)?,
+                None => node.parent()?,
+            };
+            match next {
+                Some(next) if next.interrupt_cells()?.is_some() => return Ok(Some(next)),
+                Some(next) => node = next,
+                None => return Ok(None),
+            }
+        }
+        Ok(None)
+    }
+
+    /// 解析节点的 `interrupts` 属性，按中断父节点的 `#interrupt-cells` 切分为中断说明符。
+    ///
+    /// 返回中断父节点和中断说明符迭代器。如果节点没有这个属性，返回 `None`。
+    pub fn interrupts(&self) -> Result<Option<(Self, Interrupts<'a>)>, InterruptError> {
+        let mut value = None;
+        for prop in self.raw_properties() {
+            let prop = prop?;
+            if prop.name == b"interrupts" {
+                if prop.len != prop.buf.len() * Blk::LEN {
+                    return Err(InterruptError::InvalidLength);
+                }
+                value = Some(prop.buf);
+                break;
+            }
+        }
+        let Some(value) = value else {
+            return Ok(None);
+        };
+        let parent = self.interrupt_parent()?.ok_or(InterruptError::NoParent)?;
+        let cells = parent.interrupt_cells()?.unwrap_or(0);
+        let interrupts = Interrupts::new(value, cells).ok_or(InterruptError::InvalidLength)?;
+        Ok(Some((parent, interrupts)))
+    }
+
+    /// 遍历节点的 `interrupts-extended` 属性。
+    ///
+    /// 每一项是中断控制器的引用号和按其 `#interrupt-cells` 切分的中断说明符。
+    /// 引用号为 0 的项不是占位符，报告为 [`SpecifierError::Dangling`](crate::SpecifierError::Dangling)。
+    /// 如果节点没有这个属性，迭代器为空。
+    #[inline]
+    pub fn interrupts_extended(&self) -> InterruptsExtended<'a> {
+        self.phandle_args_with("interrupts-extended", "#interrupt-cells", false)
+    }
+
+    /// 从当前节点开始，按 `interrupt-map` 将子节点的中断映射到中断控制器。
+    ///
+    /// 当前节点通常是设备的中断父节点，见 [`Self::interrupt_parent`]。
+    /// `address` 是子节点的单元地址，长度等于当前节点的 `#address-cells`；
+    /// `specifier` 是子节点的中断说明符，长度等于当前节点的 `#interrupt-cells`。
+    /// 映射逐级进行，直到遇到具有 `interrupt-controller` 属性的节点。
+    /// 返回中断控制器和它理解的中断说明符，说明符存放在 `buf` 中。
+    ///
+    /// 如果某一级没有匹配的映射项，或映射成环，返回 `None`。
+    pub fn map_interrupt<'b>(
+        &self,
+        address: &[u32],
+        specifier: &[u32],
+        buf: &'b mut [u32],
+    ) -> Result<Option<(Self, &'b [u32])>, InterruptError> {
+        let mut node = self.clone();
+        let mut address = Values::Native(address);
+        let mut specifier = Values::Native(specifier);
+        // 之后每一级的地址宽度沿用上一级映射项中的宽度
+        let mut default_address_cells = 2;
+        for _ in 0..MAX_HOPS {
+            let mut controller = false;
+            let mut map = None;
+            let mut mask = None;
+            let mut address_cells = default_address_cells;
+            let mut interrupt_cells = None;
+            for prop in node.raw_properties() {
+                let prop = prop?;
+                match (prop.name, prop.buf) {
+                    (b"interrupt-controller", _) => controller = true,
+                    (b"interrupt-map", value) => map = Some(value),
+                    (b"interrupt-map-mask", value) => mask = Some(value),
+                    (b"#address-cells", [value]) => address_cells = value.into_u32() as usize,
+                    (b"#interrupt-cells", [value]) => {
+                        interrupt_cells = Some(value.into_u32() as usize)
+                    }
+                    _ => {}
+                }
+            }
+            // 到达中断控制器
+            if controller {
+                let out = buf
+                    .get_mut(..specifier.len())
+                    .ok_or(InterruptError::BufferTooSmall)?;
+                for (i, cell) in out.iter_mut().enumerate() {
+                    *cell = specifier.get(i);
+                }
+                return Ok(Some((node, out)));
+            }
+            // 联结点
+            let invalid = InterruptError::InvalidMap(node.offset());
+            let (Some(mut map), Some(interrupt_cells)) = (map, interrupt_cells) else {
+                return Err(invalid);
+            };
+            let child_len = address_cells.saturating_add(interrupt_cells);
+            if mask.is_some_and(|mask| mask.len() != child_len) {
+                return Err(invalid);
+            }
+            if address.len() != address_cells || specifier.len() != interrupt_cells {
+                return Ok(None);
+            }
+            // 映射项的长度取决于其中断父节点，通常所有项都指向同一个
+            let mut parent = None::<(PHandle, Self, usize, usize)>;
+            let mut next = None;
+            while !map.is_empty() {
+                let Some((child, [phandle, tail @ ..])) = map.split_at_checked(child_len) else {
+                    return Err(invalid);
+                };
+                let phandle = PHandle(phandle.into_u32());
+                let (node, address_cells, interrupt_cells) = match &parent {
+                    Some((p, node, a, i)) if *p == phandle => (node.clone(), *a, *i),
+                    _ => {
+                        let node = node
+                            .find_phandle(phandle)?
+                            .ok_or(InterruptError::Dangling(phandle))?;
+                        // 中断控制器通常没有 `#address-cells`
+                        let address_cells = node.raw_u32(b"#address-cells")?.unwrap_or(0) as usize;
+                        let interrupt_cells = node
+                            .interrupt_cells()?
+                            .ok_or(InterruptError::NotController(phandle))?
+                            as usize;
+                        parent = Some((phandle, node.clone(), address_cells, interrupt_cells));
+                        (node, address_cells, interrupt_cells)
+                    }
+                };
+                let Some((parent_cells, tail)) =
+                    tail.split_at_checked(address_cells.saturating_add(interrupt_cells))
+                else {
+                    return Err(InterruptError::Truncated(phandle));
+                };
+                map = tail;
+                let matched = child.iter().enumerate().all(|(i, cell)| {
+                    let input = if i < address.len() {
+                        address.get(i)
+                    } else {
+                        specifier.get(i - address.len())
+                    };
+                    let mask = mask.map_or(u32::MAX, |mask| mask[i].into_u32());
+                    (input ^ cell.into_u32()) & mask == 0
+                });
+                if matched {
+                    let (a, s) = parent_cells.split_at(address_cells);
+                    next = Some((node, a, s));
+                    break;
+                }
+            }
+            match next {
+                Some((parent, a, s)) => {
+                    node = parent;
+                    default_address_cells = a.len();
+                    address = Values::Raw(RawCells(a));
+                    specifier = Values::Raw(RawCells(s));
+                }
+                None => return Ok(None),
+            }
+        }
+        Ok(None)
+    }
+
+    /// 节点自身的 `#interrupt-cells`。
+    #[inline]
+    pub(crate) fn interrupt_cells(&self) -> Result<Option<u32>, StructureError> {
+        self.raw_u32(b"#interrupt-cells")
+    }
+
+    /// 读取一个值为引用号的属性。
+    #[inline]
+    fn raw_phandle(&self, name: &[u8]) -> Result<Option<PHandle>, StructureError> {
+        self.raw_u32(name).map(|value| value.map(PHandle))
+    }
+}
+
+/// 映射中的单元地址或中断说明符。
+#[derive(Clone, Copy)]
+enum Values<'a> {
+    /// 调用者提供的值。
+    Native(&'a [u32]),
+    /// 从 `interrupt-map` 中切分的值。
+    Raw(RawCells<'a>),
+}
+
+impl Values<'_> {
+    #[inline]
+    fn len(&self) -> usize {
+        match self {
+            Self::Native(values) => values.len(),
+            Self::Raw(cells) => cells.len(),
+        }
+    }
+
+    #[inline]
+    fn get(&self, i: usize) -> u32 {
+        match self {
+            Self::Native(values) => values[i],
+            Self::Raw(cells) => cells.0[i].into_u32(),
+        }
+    }
+}
+
+/// 映射中断可能发现的错误类型。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum InterruptError {
+    /// 结构块不合规范。
+    Structure(StructureError),
+    /// 没有节点具有这个引用号。
+    Dangling(PHandle),
+    /// 引用的节点没有 `#interrupt-cells`，不是中断控制器。
+    NotController(PHandle),
+    /// `interrupt-map` 在这个控制器的中断说明符中间结束。
+    Truncated(PHandle),
+    /// 节点既不是中断控制器，也没有合规的 `interrupt-map`，值是节点开始标记的偏移。
+    InvalidMap(usize),
+    /// 存放中断说明符的缓冲区容量不足。
+    BufferTooSmall,
+    /// 找不到中断父节点，或 `interrupt-parent` 成环。
+    NoParent,
+    /// `interrupts` 的长度不是中断父节点 `#interrupt-cells` 的整数倍。
+    InvalidLength,
+}
+
+impl From<StructureError> for InterruptError {
+    #[inline]
+    fn from(e: StructureError) -> Self {
+        Self::Structure(e)
+    }
+}
+
+/// `interrupts-extended` 属性迭代器。
+pub type InterruptsExtended<'a> = PHandleArgs<'a, 'static>;
@@ -9,34 +9,61 @@
 //! # Usage
 //!
 //! ```rust,no_run
-//! Dtb::from_raw_parts(dtb).unwrap()
+//! # use dtb_walker::Dtb;
+//! # let dtb: *const u8 = core::ptr::null();
+//! let dtb = unsafe { Dtb::from_raw_parts(dtb) }.unwrap();
 //! ```
 
 #![no_std]
 #![deny(warnings, unstable_features, missing_docs)] // cancel this line during developing
 
+mod alias;
+mod chosen;
+mod compatible;
 mod context;
+mod events;
 mod header;
 mod indent;
+mod interrupt;
+mod mem_reserve;
+mod memory;
+mod name;
+mod node;
 mod property;
+mod reserved;
+mod specifier;
 mod str;
 mod structure_block;
 mod tree_on_stack;
 mod walker;
 
 pub use self::str::Str;
-pub use property::{PHandle, Property, Reg, StrList};
+pub use alias::{Alias, Aliases};
+pub use chosen::Chosen;
+pub use property::{
+    Interrupts, PHandle, PHandleError, PropValue, Property, Ranges, RangesEntry, RawCells, Reg,
+    RegEntry, Status, StrList, Tuples,
+};
 pub mod utils {
     //! 用于设备树解析、格式化的工具集。
 
     pub use crate::indent::indent;
 }
-pub use context::Context;
-pub use header::HeaderError;
+pub use compatible::{CompatibleMatch, FindCompatible};
+pub use context::{Context, TranslateError};
+pub use events::{Event, Events};
+pub use header::{FdtHeader, HeaderError};
+pub use interrupt::{InterruptError, InterruptsExtended};
+pub use mem_reserve::{MemReserve, MemReserveError};
+pub use memory::{MemRegion, MemoryMap, MemoryMapBuilder, MemoryMapError};
+pub use name::{NodeName, NodeNameError, UnitAddress};
+pub use node::{Children, Node, Properties};
+pub use reserved::{ReservedMemory, ReservedRegion};
+pub use specifier::{PHandleArgs, SpecifierError};
+pub use structure_block::StructureError;
 
 use core::{fmt, mem, slice};
-use header::FdtHeader;
-use property::RegCfg;
+use property::{RangesCfg, RegCfg};
 use structure_block::StructureBlock;
 use walker::Walker;
 
@@ -78,7 +105,7 @@ impl Dtb<'static> {
     pub unsafe fn from_raw_parts_unchecked(ptr: *const u8) -> Self {
         Self(slice::from_raw_parts(
             ptr,
-            (*ptr.cast::<FdtHeader>()).totalsize.into_u32() as _,
+            (*ptr.cast::<FdtHeader>()).totalsize() as _,
         ))
     }
 }
@@ -101,17 +128,13 @@ impl<'a> Dtb<'a> {
             return Err(ConvertError::Truncated);
         }
         let header = unsafe { &*slice.as_ptr().cast::<FdtHeader>() };
-        match header.verify(f) {
-            Ok(()) => {
-                let len = header.totalsize.into_u32() as usize;
-                if len <= slice.len() {
-                    Ok(Self(&slice[..len]))
-                } else {
-                    Err(ConvertError::Truncated)
-                }
-            }
-            Err(e) => Err(ConvertError::Header(e)),
+        // 检查首部会读取结构块，必须先确认切片容纳整个设备树
+        let len = header.totalsize() as usize;
+        if len > slice.len() {
+            return Err(ConvertError::Truncated);
         }
+        header.verify(f).map_err(ConvertError::Header)?;
+        Ok(Self(&slice[..len]))
     }
 
     /// 从内存切片安全地创建设备树二进制对象。
@@ -129,29 +152,190 @@ impl Dtb<'_> {
     }
 
     /// 遍历。
-    pub fn walk(&self, mut f: impl FnMut(&Context<'_>, DtbObj) -> WalkOperation) {
+    ///
+    /// # Panics
+    ///
+    /// 结构块不合规范时 panic。遍历不可信的设备树应使用 [`Dtb::try_walk`]。
+    #[inline]
+    pub fn walk(&self, f: impl FnMut(&Context<'_>, DtbObj) -> WalkOperation) {
+        self.try_walk(f).unwrap()
+    }
+
+    /// 遍历，并检查结构块是否合规。
+    ///
+    /// 任何输入都不会导致 panic。节点嵌套超过 [`Events::MAX_DEPTH`] 时返回 [`StructureError::TooDeep`]。
+    /// 如果遍历被提前终止，不再检查剩余的结构块。
+    pub fn try_walk(
+        &self,
+        mut f: impl FnMut(&Context<'_>, DtbObj) -> WalkOperation,
+    ) -> Result<(), StructureError> {
+        self.walker()?.walk_root(&mut f, false)
+    }
+
+    /// 遍历，跳过 `status` 不可用的节点及其子树。
+    ///
+    /// # Panics
+    ///
+    /// 结构块不合规范时 panic。遍历不可信的设备树应使用 [`Dtb::try_walk_available`]。
+    #[inline]
+    pub fn walk_available(&self, f: impl FnMut(&Context<'_>, DtbObj) -> WalkOperation) {
+        self.try_walk_available(f).unwrap()
+    }
+
+    /// 遍历，跳过 `status` 不可用的节点及其子树，并检查结构块是否合规。
+    ///
+    /// 不可用的节点的 [`DtbObj::SubNode`] 和属性都不会交给 `f`。
+    pub fn try_walk_available(
+        &self,
+        mut f: impl FnMut(&Context<'_>, DtbObj) -> WalkOperation,
+    ) -> Result<(), StructureError> {
+        self.walker()?.walk_root(&mut f, true)
+    }
+
+    /// 返回深度优先的遍历事件迭代器。
+    ///
+    /// 与 [`Dtb::try_walk`] 不同，迭代器不依赖闭包，可以使用迭代器组合子或同时遍历多个设备树。
+    #[inline]
+    pub fn events(&self) -> Events<'_> {
+        Events::new(self.walker())
+    }
+
+    /// 返回根节点。
+    #[inline]
+    pub fn root(&self) -> Result<Node<'_>, StructureError> {
+        Node::root(self.walker()?)
+    }
+
+    /// 按路径查找节点。
+    ///
+    /// 路径可以是绝对路径，也可以以 `/aliases` 中的别名开头，例如 `serial0`。
+    /// `:` 之后的部分视作选项（如 `stdout-path` 中的波特率）被忽略。
+    ///
+    /// 路径中的节点名可以省略单元地址，只要这样不会产生歧义。
+    /// 只有路径上的节点会被解析，其他子树都被直接跳过。
+    pub fn find_node(&self, path: &str) -> Result<Option<Node<'_>>, StructureError> {
+        let path = path.split(':').next().unwrap_or(path);
+        if let Some(path) = path.strip_prefix('/') {
+            return self.root()?.descend(path);
+        }
+        // 以别名开头
+        let (alias, rest) = path.split_once('/').unwrap_or((path, ""));
+        for a in self.aliases() {
+            let a = a?;
+            if a.name.as_bytes() == alias.as_bytes() {
+                return match a.path.as_str() {
+                    Ok(path) => match self.root()?.descend(path)? {
+                        Some(node) => node.descend(rest),
+                        None => Ok(None),
+                    },
+                    Err(_) => Ok(None),
+                };
+            }
+        }
+        Ok(None)
+    }
+
+    /// 遍历 `/aliases` 节点中的所有别名。
+    pub fn aliases(&self) -> Aliases<'_> {
+        Aliases::new(
+            self.root()
+                .and_then(|root| root.child("aliases"))
+                .map(|node| node.map(|node| node.properties())),
+        )
+    }
+
+    /// 查找引用号为 `phandle` 的节点。
+    ///
+    /// `phandle` 和 `linux,phandle` 属性都会被识别。为了发现重复的引用号，总是遍历整个设备树。
+    pub fn node_by_phandle(&self, phandle: PHandle) -> Result<Option<Node<'_>>, PHandleError> {
+        let mut found = None::<Node>;
+        let mut events = self.events();
+        while let Some(node) = events.next_node() {
+            let node = node?;
+            if node.phandle()? == Some(phandle) {
+                if found.is_some() {
+                    return Err(PHandleError::Duplicate(phandle));
+                }
+                found = Some(node);
+            }
+        }
+        Ok(found)
+    }
+
+    /// 遍历兼容 `compatible` 中任何一项的节点。
+    ///
+    /// 节点的 `compatible` 属性按从具体到一般的顺序排列，匹配结果记录第一个匹配项的位置。
+    #[inline]
+    pub fn find_compatible<'b>(&self, compatible: &'b [&'b str]) -> FindCompatible<'_, 'b> {
+        FindCompatible::new(self.events(), compatible)
+    }
+
+    /// 解析 `/chosen` 节点。
+    pub fn chosen(&self) -> Result<Option<Chosen<'_>>, StructureError> {
+        match self.root()?.child("chosen")? {
+            Some(node) => Chosen::new(&node).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// 遍历地址保护区（`/memreserve/`）中的所有项。
+    #[inline]
+    pub fn mem_reserve(&self) -> MemReserve<'_> {
+        MemReserve::new(self.0, self.header().off_mem_rsvmap())
+    }
+
+    /// 遍历 `/reserved-memory` 节点中的所有保留区。
+    pub fn reserved_memory(&self) -> ReservedMemory<'_> {
+        ReservedMemory::new(
+            self.root()
+                .and_then(|root| root.child("reserved-memory"))
+                .map(|node| node.map(|node| node.children())),
+        )
+    }
+
+    /// 构建物理内存布局。
+    #[inline]
+    pub fn memory_map(&self) -> MemoryMapBuilder<'_, '_> {
+        MemoryMapBuilder::new(self)
+    }
+
+    /// 切分结构块和字符串区，构造从结构块起点开始的游标。
+    fn walker(&self) -> Result<Walker<'_>, StructureError> {
         let header = self.header();
-        let off_struct = header.off_dt_struct.into_u32() as usize;
-        let len_struct = header.size_dt_struct.into_u32() as usize;
-        let off_strings = header.off_dt_strings.into_u32() as usize;
-        let len_strings = header.size_dt_strings.into_u32() as usize;
-        Walker {
-            tail: unsafe {
-                slice::from_raw_parts(
-                    self.0[off_struct..]
-                        .as_ptr()
-                        .cast::<StructureBlock>()
-                        .offset(2),
-                    len_struct / StructureBlock::LEN - 3,
-                )
-            },
-            strings: &self.0[off_strings..][..len_strings],
+        let off_struct = header.off_dt_struct() as usize;
+        let off_strings = header.off_dt_strings() as usize;
+        // 旧版本首部不记录结构块和字符串区的大小，只能以设备树结尾为界
+        let structure = self
+            .0
+            .get(off_struct..)
+            .and_then(|s| match header.size_dt_struct() {
+                Some(len) => s.get(..len as usize),
+                None => Some(s),
+            })
+            .ok_or(StructureError::OutOfBounds)?;
+        let strings = self
+            .0
+            .get(off_strings..)
+            .and_then(|s| match header.size_dt_strings() {
+                Some(len) => s.get(..len as usize),
+                None => Some(s),
+            })
+            .ok_or(StructureError::OutOfBounds)?;
+        if !is_aligned(structure.as_ptr() as _, StructureBlock::LEN) {
+            return Err(StructureError::Misaligned);
         }
-        .walk_inner(&mut f, Some(Context::ROOT));
+        let structure = unsafe {
+            slice::from_raw_parts(
+                structure.as_ptr().cast::<StructureBlock>(),
+                structure.len() / StructureBlock::LEN,
+            )
+        };
+        Ok(Walker::new(structure, strings, header.is_legacy()))
     }
 
+    /// 返回设备树首部。
     #[inline]
-    fn header(&self) -> &FdtHeader {
+    pub fn header(&self) -> &FdtHeader {
         unsafe { &*self.0.as_ptr().cast() }
     }
 }
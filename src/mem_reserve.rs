@@ -0,0 +1,50 @@
+﻿//! §5.3
+
+/// 地址保护区解析可能发现的错误类型。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum MemReserveError {
+    /// 地址保护区偏移超出设备树范围。
+    OutOfBounds(u32),
+    /// 设备树结束前没有找到全 0 的结尾项。
+    Unterminated,
+}
+
+/// 地址保护区迭代器，产生 `(address, size)` 对。
+pub struct MemReserve<'a>(Option<Result<&'a [u8], MemReserveError>>);
+
+impl<'a> MemReserve<'a> {
+    /// 一个地址保护区项的字节长度。
+    const ENTRY_LEN: usize = 16;
+
+    #[inline]
+    pub(crate) fn new(dtb: &'a [u8], offset: u32) -> Self {
+        Self(Some(
+            dtb.get(offset as usize..)
+                .ok_or(MemReserveError::OutOfBounds(offset)),
+        ))
+    }
+}
+
+impl Iterator for MemReserve<'_> {
+    type Item = Result<(u64, u64), MemReserveError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let buf = match self.0.take()? {
+            Ok(buf) => buf,
+            Err(e) => return Some(Err(e)),
+        };
+        if buf.len() < Self::ENTRY_LEN {
+            return Some(Err(MemReserveError::Unterminated));
+        }
+        let (entry, tail) = buf.split_at(Self::ENTRY_LEN);
+        let (address, size) = entry.split_at(8);
+        let address = u64::from_be_bytes(address.try_into().unwrap());
+        let size = u64::from_be_bytes(size.try_into().unwrap());
+        if address == 0 && size == 0 {
+            None
+        } else {
+            self.0 = Some(Ok(tail));
+            Some(Ok((address, size)))
+        }
+    }
+}
@@ -0,0 +1,294 @@
+﻿//! §3.4 §3.5 §5.3
+
+use crate::{Dtb, MemReserveError, Node, Property, RegEntry, StructureError};
+
+/// 一段物理地址区间 `[start, end)`。
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct MemRegion {
+    /// 起始地址。
+    pub start: u64,
+    /// 结束地址，不包含在区间内。
+    pub end: u64,
+}
+
+impl MemRegion {
+    /// 从起始地址和长度构造区间。长度超出地址空间的部分被舍弃。
+    #[inline]
+    pub const fn new(start: u64, size: u64) -> Self {
+        Self {
+            start,
+            end: start.saturating_add(size),
+        }
+    }
+
+    /// 如果区间为空则返回 `true`。
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.start >= self.end
+    }
+}
+
+/// 构建物理内存布局可能发现的错误类型。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum MemoryMapError {
+    /// 结构块不合规范。
+    Structure(StructureError),
+    /// 地址保护区不合规范。
+    MemReserve(MemReserveError),
+    /// 可用区间的缓冲区容量不足。
+    UsableOverflow,
+    /// 保留区间的缓冲区容量不足。
+    ReservedOverflow,
+}
+
+impl From<StructureError> for MemoryMapError {
+    #[inline]
+    fn from(e: StructureError) -> Self {
+        Self::Structure(e)
+    }
+}
+
+impl From<MemReserveError> for MemoryMapError {
+    #[inline]
+    fn from(e: MemReserveError) -> Self {
+        Self::MemReserve(e)
+    }
+}
+
+/// 物理内存布局。
+pub struct MemoryMap<'b> {
+    /// 排序、合并后的可用区间。
+    pub usable: &'b [MemRegion],
+    /// 排序、合并后的保留区间，包括 `reusable` 的区间。
+    pub reserved: &'b [MemRegion],
+}
+
+/// 物理内存布局构建器。
+///
+/// 可用区间来自 `device_type = "memory"` 的节点，
+/// 保留区间来自地址保护区和 `/reserved-memory` 中所有可用的静态区间。
+/// 除了标记了 `reusable` 的区间，所有保留区间都从可用区间中扣除。
+/// 动态区间在分配之前没有地址，不在其中。
+pub struct MemoryMapBuilder<'d, 'a> {
+    dtb: &'d Dtb<'a>,
+    align: u64,
+}
+
+impl<'d, 'a> MemoryMapBuilder<'d, 'a> {
+    #[inline]
+    pub(crate) fn new(dtb: &'d Dtb<'a>) -> Self {
+        Self { dtb, align: 1 }
+    }
+
+    /// 设置对齐。可用区间向内对齐，保留区间向外对齐。
+    #[inline]
+    pub fn align(mut self, align: u64) -> Self {
+        self.align = align.max(1);
+        self
+    }
+
+    /// 将物理内存布局写入缓冲区。
+    ///
+    /// 缓冲区容量不足时返回错误而不是截断。
+    pub fn build<'b>(
+        self,
+        usable: &'b mut [MemRegion],
+        reserved: &'b mut [MemRegion],
+    ) -> Result<MemoryMap<'b>, MemoryMapError> {
+        use MemoryMapError as E;
+
+        let mut usable = RegionSet::new(usable);
+        let mut reserved = RegionSet::new(reserved);
+        // 可用区间
+        for child in self.dtb.root()?.children() {
+            let child = child?;
+            let mut memory = false;
+            let mut available = true;
+            for prop in child.properties() {
+                match prop? {
+                    Property::DeviceType(device_type) => {
+                        memory = device_type.as_bytes() == b"memory";
+                    }
+                    Property::Status(status) => available = status.is_available(),
+                    _ => {}
+                }
+            }
+            if memory && available {
+                for region in regions(&child)? {
+                    usable.insert(region).map_err(|()| E::UsableOverflow)?;
+                }
+            }
+        }
+        // 保留区间
+        let mut reserve = |region: MemRegion, reusable: bool| {
+            // 长度为 0 的区间对齐后也不保留任何地址
+            if region.is_empty() {
+                return Ok(());
+            }
+            let region = self.expand(region);
+            reserved.insert(region).map_err(|()| E::ReservedOverflow)?;
+            if !reusable {
+                usable.remove(region).map_err(|()| E::UsableOverflow)?;
+            }
+            Ok::<_, MemoryMapError>(())
+        };
+        for entry in self.dtb.mem_reserve() {
+            let (address, size) = entry?;
+            reserve(MemRegion::new(address, size), false)?;
+        }
+        for region in self.dtb.reserved_memory() {
+            let region = region?;
+            if !region.status.is_available() {
+                continue;
+            }
+            for entry in region.reg.into_iter().flatten().filter_map(mem_region) {
+                reserve(entry, region.reusable)?;
+            }
+        }
+        usable.shrink(self.align);
+        Ok(MemoryMap {
+            usable: usable.into_slice(),
+            reserved: reserved.into_slice(),
+        })
+    }
+
+    /// 向外对齐。
+    fn expand(&self, region: MemRegion) -> MemRegion {
+        MemRegion {
+            start: region.start - region.start % self.align,
+            end: region
+                .end
+                .checked_next_multiple_of(self.align)
+                .unwrap_or(u64::MAX),
+        }
+    }
+}
+
+/// 节点 `reg` 属性描述的所有区间。
+fn regions<'a>(node: &Node<'a>) -> Result<impl Iterator<Item = MemRegion> + 'a, StructureError> {
+    let mut reg = None;
+    for prop in node.properties() {
+        if let Property::Reg(r) = prop? {
+            reg = Some(r);
+            break;
+        }
+    }
+    Ok(reg.into_iter().flatten().filter_map(mem_region))
+}
+
+/// `reg` 中的一项描述的区间。没有长度或地址超出 `u64` 的项被忽略。
+#[inline]
+fn mem_region(entry: RegEntry) -> Option<MemRegion> {
+    Some(MemRegion::new(entry.address()?, entry.size()?))
+}
+
+/// 排序、合并的区间集合。
+struct RegionSet<'b> {
+    buf: &'b mut [MemRegion],
+    len: usize,
+}
+
+impl<'b> RegionSet<'b> {
+    #[inline]
+    fn new(buf: &'b mut [MemRegion]) -> Self {
+        Self { buf, len: 0 }
+    }
+
+    #[inline]
+    fn as_slice(&self) -> &[MemRegion] {
+        &self.buf[..self.len]
+    }
+
+    #[inline]
+    fn into_slice(self) -> &'b [MemRegion] {
+        &self.buf[..self.len]
+    }
+
+    /// 并入一个区间。
+    fn insert(&mut self, region: MemRegion) -> Result<(), ()> {
+        if region.is_empty() {
+            return Ok(());
+        }
+        // 第一个与 `region` 重叠或相邻的区间
+        let first = self.as_slice().partition_point(|r| r.end < region.start);
+        // 第一个在 `region` 之后且不相邻的区间
+        let last = first + self.buf[first..self.len].partition_point(|r| r.start <= region.end);
+        if first == last {
+            if self.len == self.buf.len() {
+                return Err(());
+            }
+            self.buf[first..=self.len].rotate_right(1);
+            self.buf[first] = region;
+            self.len += 1;
+        } else {
+            self.buf[first] = MemRegion {
+                start: region.start.min(self.buf[first].start),
+                end: region.end.max(self.buf[last - 1].end),
+            };
+            self.buf[first + 1..self.len].rotate_left(last - first - 1);
+            self.len -= last - first - 1;
+        }
+        Ok(())
+    }
+
+    /// 扣除一个区间。
+    fn remove(&mut self, region: MemRegion) -> Result<(), ()> {
+        if region.is_empty() {
+            return Ok(());
+        }
+        let mut i = self.as_slice().partition_point(|r| r.end <= region.start);
+        while i < self.len && self.buf[i].start < region.end {
+            let r = self.buf[i];
+            let left = MemRegion {
+                start: r.start,
+                end: region.start,
+            };
+            let right = MemRegion {
+                start: region.end,
+                end: r.end,
+            };
+            match (left.is_empty(), right.is_empty()) {
+                (false, false) => {
+                    if self.len == self.buf.len() {
+                        return Err(());
+                    }
+                    self.buf[i..=self.len].rotate_right(1);
+                    self.buf[i] = left;
+                    self.buf[i + 1] = right;
+                    self.len += 1;
+                    i += 2;
+                }
+                (false, true) => {
+                    self.buf[i] = left;
+                    i += 1;
+                }
+                (true, false) => {
+                    self.buf[i] = right;
+                    i += 1;
+                }
+                (true, true) => {
+                    self.buf[i..self.len].rotate_left(1);
+                    self.len -= 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 所有区间向内对齐，舍弃对齐后为空的区间。
+    fn shrink(&mut self, align: u64) {
+        let mut len = 0;
+        for i in 0..self.len {
+            let r = self.buf[i];
+            let r = MemRegion {
+                start: r.start.checked_next_multiple_of(align).unwrap_or(u64::MAX),
+                end: r.end - r.end % align,
+            };
+            if !r.is_empty() {
+                self.buf[len] = r;
+                len += 1;
+            }
+        }
+        self.len = len;
+    }
+}
@@ -0,0 +1,215 @@
+﻿//! §2.2.1
+
+use crate::{Node, Property, RawCells, Str, StructureError};
+use core::fmt;
+
+/// 节点名本体的最大长度。
+const MAX_BASE_LEN: usize = 31;
+
+impl<'a> Node<'a> {
+    /// 返回拆分为本体和单元地址的节点名。
+    #[inline]
+    pub fn node_name(&self) -> NodeName<'a> {
+        NodeName::new(self.name())
+    }
+
+    /// 检查节点名是否合规，包括单元地址是否与第一个 `reg` 项一致。
+    ///
+    /// 有 `reg` 的节点必须有单元地址，没有 `reg` 的节点不能有单元地址。
+    /// 单元地址可以是一个十六进制数，也可以是逗号分隔的每个地址 cell；
+    /// 3 个 cell 的 PCI 地址按 `设备号[,功能号]` 比较。
+    pub fn validate_name(&self) -> Result<(), NodeNameError> {
+        let name = self.node_name();
+        name.validate()?;
+        let mut first = None;
+        for prop in self.properties() {
+            if let Property::Reg(mut reg) = prop? {
+                first = reg.next();
+                break;
+            }
+        }
+        match (name.unit_address(), first) {
+            (None, None) => Ok(()),
+            (None, Some(_)) => Err(NodeNameError::MissingUnitAddress),
+            (Some(_), None) => Err(NodeNameError::UnexpectedUnitAddress),
+            (Some(unit), Some(entry)) if unit.matches(entry.raw_address()) => Ok(()),
+            (Some(_), Some(_)) => Err(NodeNameError::UnitAddressMismatch),
+        }
+    }
+}
+
+/// 拆分的节点名，形如 `node-name@unit-address`。
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct NodeName<'a> {
+    base: Str<'a>,
+    unit_address: Option<Str<'a>>,
+}
+
+impl<'a> NodeName<'a> {
+    /// 在第一个 `@` 处拆分节点名。
+    pub fn new(name: Str<'a>) -> Self {
+        match name.0.iter().position(|c| *c == b'@') {
+            Some(i) => Self {
+                base: Str(&name.0[..i]),
+                unit_address: Some(Str(&name.0[i + 1..])),
+            },
+            None => Self {
+                base: name,
+                unit_address: None,
+            },
+        }
+    }
+
+    /// 节点名本体。
+    #[inline]
+    pub fn base(&self) -> Str<'a> {
+        self.base
+    }
+
+    /// 单元地址。
+    #[inline]
+    pub fn unit_address(&self) -> Option<UnitAddress<'a>> {
+        self.unit_address.map(|s| UnitAddress(s.0))
+    }
+
+    /// 检查节点名的长度和字符集。根节点的节点名为空，也是合规的。
+    pub fn validate(&self) -> Result<(), NodeNameError> {
+        use NodeNameError as E;
+        match self.base.0 {
+            [] if self.unit_address.is_none() => return Ok(()),
+            [] => return Err(E::Empty),
+            base if base.len() > MAX_BASE_LEN => return Err(E::TooLong),
+            [first, ..] if !first.is_ascii_alphabetic() => return Err(E::InvalidStart(*first)),
+            base => check_chars(base)?,
+        }
+        match self.unit_address {
+            Some(Str([])) => Err(E::EmptyUnitAddress),
+            Some(unit) => check_chars(unit.0),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for NodeName<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.base.fmt(f)?;
+        match self.unit_address {
+            Some(unit) => write!(f, "@{unit}"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// 单元地址，可以由逗号分隔的多个部分组成，例如 PCI 的 `1,0`。
+///
+/// 迭代器依次产生按十六进制解析的每个部分，不是十六进制数或超出 `u64` 范围的部分为 `None`。
+#[derive(Clone, PartialEq, Eq)]
+pub struct UnitAddress<'a>(&'a [u8]);
+
+impl<'a> UnitAddress<'a> {
+    /// 单元地址的字符串。
+    #[inline]
+    pub fn as_str(&self) -> Str<'a> {
+        Str(self.0)
+    }
+
+    /// 将只有一个部分的单元地址按十六进制解析。
+    #[inline]
+    pub fn to_u64(&self) -> Option<u64> {
+        parse_hex(self.0)
+    }
+
+    /// 单元地址是否与 `reg` 项的地址一致。
+    fn matches(&self, address: RawCells) -> bool {
+        let count = self.clone().count();
+        let mut parts = self.clone();
+        // PCI 地址的第一个 cell 包含设备号和功能号
+        if let (3, 1..=2, Some(hi)) = (address.len(), count, address.get(0)) {
+            let device = parts.next().flatten();
+            let function = parts.next().unwrap_or(Some(0));
+            return device == Some((hi >> 11 & 0x1f) as _) && function == Some((hi >> 8 & 7) as _);
+        }
+        if count == 1 {
+            return self
+                .to_u64()
+                .is_some_and(|unit| Some(unit) == address.to_u64());
+        }
+        count == address.len() && parts.zip(address.iter()).all(|(p, c)| p == Some(c as _))
+    }
+}
+
+impl Iterator for UnitAddress<'_> {
+    type Item = Option<u64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.is_empty() {
+            return None;
+        }
+        let (part, tail) = match self.0.iter().position(|c| *c == b',') {
+            Some(i) => (&self.0[..i], &self.0[i + 1..]),
+            None => (self.0, &[][..]),
+        };
+        self.0 = tail;
+        Some(parse_hex(part))
+    }
+}
+
+impl fmt::Display for UnitAddress<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+/// 检查节点名可能发现的错误类型。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum NodeNameError {
+    /// 结构块不合规范。
+    Structure(StructureError),
+    /// 非根节点的节点名本体为空。
+    Empty,
+    /// 节点名本体超过 31 个字符。
+    TooLong,
+    /// 节点名本体不以字母开头。
+    InvalidStart(u8),
+    /// 节点名中有规范之外的字符。
+    InvalidChar(u8),
+    /// `@` 之后的单元地址为空。
+    EmptyUnitAddress,
+    /// 节点有 `reg`，但节点名没有单元地址。
+    MissingUnitAddress,
+    /// 节点没有 `reg`，但节点名有单元地址。
+    UnexpectedUnitAddress,
+    /// 单元地址与第一个 `reg` 项的地址不一致。
+    UnitAddressMismatch,
+}
+
+impl From<StructureError> for NodeNameError {
+    #[inline]
+    fn from(e: StructureError) -> Self {
+        Self::Structure(e)
+    }
+}
+
+/// 检查节点名中的字符。
+fn check_chars(s: &[u8]) -> Result<(), NodeNameError> {
+    match s
+        .iter()
+        .find(|c| !c.is_ascii_alphanumeric() && !b",._+-".contains(c))
+    {
+        Some(c) => Err(NodeNameError::InvalidChar(*c)),
+        None => Ok(()),
+    }
+}
+
+/// 解析不带前缀的十六进制数。
+fn parse_hex(s: &[u8]) -> Option<u64> {
+    if s.is_empty() {
+        return None;
+    }
+    s.iter().try_fold(0u64, |acc, c| {
+        let digit = (*c as char).to_digit(16)?;
+        acc.checked_mul(16)?.checked_add(digit as _)
+    })
+}
@@ -0,0 +1,469 @@
+﻿use crate::{
+    context::{Bus, Cells},
+    walker::{parse_prop, Token, Walker},
+    Context, Events, PHandle, PropValue, Property, Status, Str, StructureBlock as Blk,
+    StructureError,
+};
+use core::fmt;
+
+/// 设备树节点。
+///
+/// 节点只记录其在结构块中的位置，属性和子节点在访问时才解析。
+#[derive(Clone)]
+pub struct Node<'a> {
+    /// 节点名之后的游标。
+    walker: Walker<'a>,
+    name: Str<'a>,
+    /// 节点开始标记的偏移。
+    offset: usize,
+    /// 父节点的 cells。
+    parent: Cells,
+}
+
+impl<'a> Node<'a> {
+    #[inline]
+    pub(crate) fn new(walker: Walker<'a>, name: Str<'a>, offset: usize, parent: Cells) -> Self {
+        Self {
+            walker,
+            name,
+            offset,
+            parent,
+        }
+    }
+
+    /// 构造根节点。
+    pub(crate) fn root(mut walker: Walker<'a>) -> Result<Self, StructureError> {
+        let offset = walker.offset();
+        match walker.next_token()? {
+            Token::Begin(name) => Ok(Self::new(walker, name, offset, Cells::DEFAULT)),
+            _ => Err(StructureError::Unbalanced { offset }),
+        }
+    }
+
+    /// 返回节点名。
+    #[inline]
+    pub fn name(&self) -> Str<'a> {
+        self.name
+    }
+
+    /// 返回节点开始标记相对结构块起始的字节偏移。
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// 返回节点的引用号，来自 `phandle` 或 `linux,phandle` 属性。
+    pub fn phandle(&self) -> Result<Option<PHandle>, StructureError> {
+        for prop in self.raw_properties() {
+            if let RawProperty {
+                name: b"phandle" | b"linux,phandle",
+                buf: [value],
+                ..
+            } = prop?
+            {
+                return Ok(Some(PHandle(value.into_u32())));
+            }
+        }
+        Ok(None)
+    }
+
+    /// 返回节点的状态，来自 `status` 属性。
+    #[inline]
+    pub fn status(&self) -> Result<Status<'a>, StructureError> {
+        self.raw_status(b"status").map(Option::unwrap_or_default)
+    }
+
+    /// 返回节点在安全世界中的状态，来自 `secure-status` 属性，缺省与 `status` 相同。
+    pub fn secure_status(&self) -> Result<Status<'a>, StructureError> {
+        match self.raw_status(b"secure-status")? {
+            Some(status) => Ok(status),
+            None => self.status(),
+        }
+    }
+
+    /// 读取一个状态属性。
+    fn raw_status(&self, name: &[u8]) -> Result<Option<Status<'a>>, StructureError> {
+        for prop in self.raw_properties() {
+            let prop = prop?;
+            if prop.name == name {
+                let value = prop.value();
+                let status = value.as_str().unwrap_or(Str(value.as_bytes()));
+                return Ok(Some(Status::new(status)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// 读取一个值为单个 cell 的属性。
+    pub(crate) fn raw_u32(&self, name: &[u8]) -> Result<Option<u32>, StructureError> {
+        for prop in self.raw_properties() {
+            let prop = prop?;
+            if prop.name == name {
+                return Ok(prop.value().as_u32());
+            }
+        }
+        Ok(None)
+    }
+
+    /// 返回父节点。根节点没有父节点。
+    ///
+    /// 节点不保存其祖先，所以需要从根节点重新找到父节点。
+    pub(crate) fn parent(&self) -> Result<Option<Self>, StructureError> {
+        let root = Self::root(self.walker.rewind())?;
+        if root.offset == self.offset {
+            Ok(None)
+        } else {
+            root.enclosing(self.offset).map(Some)
+        }
+    }
+
+    /// 从当前节点向下，找到严格包含偏移 `target` 的最深的节点。
+    ///
+    /// `target` 应该是某个属性的偏移，或者某个子孙节点开始标记的偏移，此时找到的是其父节点。
+    pub(crate) fn enclosing(&self, target: usize) -> Result<Self, StructureError> {
+        let mut node = self.clone();
+        loop {
+            // 目标在最后一个早于它开始的子节点中
+            let mut next = None;
+            for child in node.children() {
+                let child = child?;
+                if child.offset == target {
+                    return Ok(node);
+                }
+                if child.offset > target {
+                    break;
+                }
+                next = Some(child);
+            }
+            match next {
+                Some(child) => node = child,
+                None => return Ok(node),
+            }
+        }
+    }
+
+    /// 查找引用号为 `phandle` 的节点。
+    ///
+    /// 只解析 `phandle` 和 `linux,phandle` 属性，不检查重复的引用号。
+    pub(crate) fn find_phandle(&self, phandle: PHandle) -> Result<Option<Self>, StructureError> {
+        let root = Self::root(self.walker.rewind())?;
+        let mut walker = root.walker.clone();
+        let mut begin = root.offset;
+        loop {
+            let offset = walker.offset();
+            match walker.next_token()? {
+                Token::Begin(_) => begin = offset,
+                Token::Prop {
+                    name: b"phandle" | b"linux,phandle",
+                    value: [value],
+                    ..
+                } if value.into_u32() == phandle.0 => {
+                    // 开始标记之后至少还有节点名，所以 `begin + 1` 只在这个节点中
+                    return root.enclosing(begin + 1).map(Some);
+                }
+                Token::Finish => return Ok(None),
+                _ => {}
+            }
+        }
+    }
+
+    /// 构造节点的遍历上下文，交给 `f` 使用。
+    ///
+    /// 上下文与 [`Dtb::walk`](crate::Dtb::walk) 访问此节点的属性时得到的相同。
+    /// 节点不保存其祖先，所以需要从根节点重新找到此节点。
+    pub fn with_context<R>(&self, f: impl FnOnce(&Context<'_>) -> R) -> Result<R, StructureError> {
+        let root = Self::root(self.walker.rewind())?;
+        let ctx = Context::root(root.walker.interrupt_parent(None, &mut None)?);
+        root.find_context(&ctx, self.offset, f, 1)
+    }
+
+    /// `depth` 是从根节点到当前节点的节点数量，与 [`Events::MAX_DEPTH`] 的限制相同。
+    fn find_context<R>(
+        &self,
+        ctx: &Context<'_>,
+        target: usize,
+        f: impl FnOnce(&Context<'_>) -> R,
+        depth: usize,
+    ) -> Result<R, StructureError> {
+        if self.offset == target {
+            return Ok(f(ctx));
+        }
+        // 目标在最后一个不晚于它开始的子节点中
+        let mut next = None;
+        for child in self.children() {
+            let child = child?;
+            if child.offset > target {
+                break;
+            }
+            next = Some(child);
+        }
+        match next {
+            Some(child) if depth == Events::MAX_DEPTH => Err(StructureError::TooDeep {
+                offset: child.offset,
+            }),
+            Some(child) => {
+                let bus = self.bus(ctx)?;
+                let interrupt_parent = child
+                    .walker
+                    .interrupt_parent(bus.interrupt_parent, &mut None)?;
+                let ctx = ctx.grow(child.name, child.parent, bus, interrupt_parent);
+                child.find_context(&ctx, target, f, depth + 1)
+            }
+            None => Err(StructureError::Unbalanced { offset: target }),
+        }
+    }
+
+    /// 节点作为总线的地址映射。`ctx` 是节点自身的上下文。
+    fn bus(&self, ctx: &Context) -> Result<Bus<'a>, StructureError> {
+        let interrupt_parent = ctx.interrupt_parent();
+        let mut bus = Bus::new(ctx.bus().dma_coherent, interrupt_parent);
+        let mut properties = self.properties();
+        properties.interrupt_cells = Some(interrupt_parent.map(|p| p.cells));
+        for prop in properties {
+            bus.absorb(&prop?);
+        }
+        Ok(bus)
+    }
+
+    /// 如果节点或其祖先有 `dma-coherent` 属性则返回 `true`。
+    ///
+    /// 节点自身或更近的祖先的 `dma-noncoherent` 属性优先。
+    pub fn is_dma_coherent(&self) -> Result<bool, StructureError> {
+        for prop in self.properties() {
+            match prop? {
+                Property::DmaCoherent => return Ok(true),
+                Property::DmaNonCoherent => return Ok(false),
+                _ => {}
+            }
+        }
+        self.with_context(|ctx| ctx.is_dma_coherent())
+    }
+
+    /// 遍历节点的属性。
+    #[inline]
+    pub fn properties(&self) -> Properties<'a> {
+        Properties {
+            node: self.clone(),
+            raw: self.raw_properties(),
+            parent: self.parent,
+            cells: Cells::DEFAULT,
+            interrupt_cells: None,
+        }
+    }
+
+    /// 遍历节点未解析的属性。
+    #[inline]
+    pub(crate) fn raw_properties(&self) -> RawProperties<'a> {
+        RawProperties {
+            walker: self.walker.clone(),
+            done: false,
+        }
+    }
+
+    /// 返回父节点的 cells。
+    #[inline]
+    pub(crate) fn parent_cells(&self) -> Cells {
+        self.parent
+    }
+
+    /// 遍历节点的子节点。
+    #[inline]
+    pub fn children(&self) -> Children<'a> {
+        Children {
+            walker: self.walker.clone(),
+            cells: Cells::DEFAULT,
+            done: false,
+        }
+    }
+
+    /// 查找名为 `name` 的子节点。
+    ///
+    /// 如果 `name` 不含单元地址，也可以匹配唯一一个节点名去掉单元地址后等于 `name` 的子节点。
+    pub fn child(&self, name: &str) -> Result<Option<Self>, StructureError> {
+        let name = name.as_bytes();
+        let mut candidate = None;
+        let mut ambiguous = false;
+        for child in self.children() {
+            let child = child?;
+            let bytes = child.name.as_bytes();
+            if bytes == name {
+                return Ok(Some(child));
+            }
+            if !name.contains(&b'@') && child.node_name().base().as_bytes() == name {
+                ambiguous = candidate.is_some();
+                candidate = Some(child);
+            }
+        }
+        Ok(if ambiguous { None } else { candidate })
+    }
+
+    /// 按从当前节点出发的相对路径查找节点。
+    pub(crate) fn descend(&self, path: &str) -> Result<Option<Self>, StructureError> {
+        let mut node = self.clone();
+        for name in path.split('/').filter(|name| !name.is_empty()) {
+            match node.child(name)? {
+                Some(child) => node = child,
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(node))
+    }
+}
+
+impl fmt::Display for Node<'_> {
+    /// 格式化节点的绝对路径。
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.with_context(|ctx| {
+            if ctx.is_root() {
+                '/'.fmt(f)
+            } else {
+                ctx.fmt(f)
+            }
+        })
+        .unwrap_or(Err(fmt::Error))
+    }
+}
+
+/// 节点属性迭代器。
+///
+/// 遇到 `interrupts` 时才查找节点的中断父节点。
+pub struct Properties<'a> {
+    node: Node<'a>,
+    raw: RawProperties<'a>,
+    parent: Cells,
+    cells: Cells,
+    /// 已经找到的中断父节点的 `#interrupt-cells`。
+    interrupt_cells: Option<Option<u32>>,
+}
+
+impl<'a> Properties<'a> {
+    fn interrupt_cells(&mut self) -> Result<Option<u32>, StructureError> {
+        if let Some(cells) = self.interrupt_cells {
+            return Ok(cells);
+        }
+        let cells = match self.node.interrupt_parent()? {
+            Some(parent) => parent.interrupt_cells()?,
+            None => None,
+        };
+        self.interrupt_cells = Some(cells);
+        Ok(cells)
+    }
+}
+
+impl<'a> Iterator for Properties<'a> {
+    type Item = Result<Property<'a>, StructureError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let RawProperty { name, buf, len } = match self.raw.next()? {
+            Ok(prop) => prop,
+            Err(e) => return Some(Err(e)),
+        };
+        let interrupt_cells = match name {
+            b"interrupts" => match self.interrupt_cells() {
+                Ok(cells) => cells,
+                Err(e) => return Some(Err(e)),
+            },
+            _ => None,
+        };
+        Some(Ok(parse_prop(
+            name,
+            buf,
+            len,
+            self.parent,
+            &mut self.cells,
+            interrupt_cells,
+            &self.raw.walker,
+        )))
+    }
+}
+
+/// 未解析的属性。
+pub(crate) struct RawProperty<'a> {
+    pub name: &'a [u8],
+    pub buf: &'a [Blk],
+    pub len: usize,
+}
+
+impl<'a> RawProperty<'a> {
+    /// 属性值。
+    #[inline]
+    pub fn value(&self) -> PropValue<'a> {
+        PropValue::new(self.buf, self.len)
+    }
+}
+
+/// 未解析的节点属性迭代器。
+pub(crate) struct RawProperties<'a> {
+    walker: Walker<'a>,
+    done: bool,
+}
+
+impl<'a> Iterator for RawProperties<'a> {
+    type Item = Result<RawProperty<'a>, StructureError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let offset = self.walker.offset();
+        let err = match self.walker.next_token() {
+            Ok(Token::Prop { name, value, len }) => {
+                return Some(Ok(RawProperty {
+                    name,
+                    buf: value,
+                    len,
+                }))
+            }
+            // 属性总在子节点之前
+            Ok(Token::Begin(_) | Token::End) => {
+                self.done = true;
+                return None;
+            }
+            Ok(Token::Finish) => StructureError::Unbalanced { offset },
+            Err(e) => e,
+        };
+        self.done = true;
+        Some(Err(err))
+    }
+}
+
+/// 子节点迭代器。
+pub struct Children<'a> {
+    walker: Walker<'a>,
+    /// 当前节点的 cells。
+    cells: Cells,
+    done: bool,
+}
+
+impl<'a> Iterator for Children<'a> {
+    type Item = Result<Node<'a>, StructureError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let offset = self.walker.offset();
+            let err = match self.walker.next_token() {
+                Ok(Token::Prop { name, value, len }) => {
+                    self.cells.absorb(name, value, len);
+                    continue;
+                }
+                Ok(Token::Begin(name)) => {
+                    let child = Node::new(self.walker.clone(), name, offset, self.cells);
+                    // 跳过子节点的子树
+                    match self.walker.skip_node() {
+                        Ok(()) => return Some(Ok(child)),
+                        Err(e) => e,
+                    }
+                }
+                Ok(Token::End) => {
+                    self.done = true;
+                    return None;
+                }
+                Ok(Token::Finish) => StructureError::Unbalanced { offset },
+                Err(e) => e,
+            };
+            self.done = true;
+            return Some(Err(err));
+        }
+        None
+    }
+}
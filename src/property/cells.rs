@@ -0,0 +1,79 @@
+﻿use crate::StructureBlock;
+use core::fmt;
+
+/// 由若干个 cell 组成的数值，例如总线地址。
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct RawCells<'a>(pub(crate) &'a [StructureBlock]);
+
+impl<'a> RawCells<'a> {
+    /// cell 的数量。
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// 如果没有 cell 则返回 `true`。
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// 返回第 `i` 个 cell。
+    #[inline]
+    pub fn get(&self, i: usize) -> Option<u32> {
+        self.0.get(i).map(|blk| blk.into_u32())
+    }
+
+    /// 依次返回每个 cell。
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = u32> + 'a {
+        self.0.iter().map(|blk| blk.into_u32())
+    }
+
+    /// 转换为 `u64`。如果数值超出 `u64` 的范围则返回 `None`。
+    pub fn to_u64(&self) -> Option<u64> {
+        let split = self.0.len().saturating_sub(2);
+        let (high, low) = self.0.split_at(split);
+        if high.iter().any(|blk| blk.into_u32() != 0) {
+            return None;
+        }
+        Some(
+            low.iter()
+                .fold(0, |acc, blk| (acc << 32) | blk.into_u32() as u64),
+        )
+    }
+}
+
+impl RawCells<'_> {
+    /// 低 2 个 cell 组成的 `u64`，忽略更高的 cell。
+    ///
+    /// 用于 3 个 cell 的 PCI 地址，其第一个 cell 描述地址空间。
+    #[inline]
+    pub fn low_u64(&self) -> u64 {
+        let skip = self.0.len().saturating_sub(2);
+        self.0[skip..]
+            .iter()
+            .fold(0, |acc, blk| (acc << 32) | blk.into_u32() as u64)
+    }
+}
+
+impl<'a> RawCells<'a> {
+    /// 低 2 个 cell 之外的高位 cell。
+    #[inline]
+    pub(crate) fn high(&self) -> RawCells<'a> {
+        RawCells(&self.0[..self.0.len().saturating_sub(2)])
+    }
+}
+
+impl fmt::Debug for RawCells<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<")?;
+        for (i, cell) in self.iter().enumerate() {
+            if i != 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{cell:#x}")?;
+        }
+        write!(f, ">")
+    }
+}
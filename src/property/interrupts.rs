@@ -0,0 +1,56 @@
+﻿use super::RawCells;
+use crate::StructureBlock;
+use core::fmt;
+
+/// `interrupts` 属性，按中断父节点的 `#interrupt-cells` 切分为中断说明符。
+#[derive(Clone)]
+pub struct Interrupts<'a> {
+    buf: &'a [StructureBlock],
+    cells: u32,
+}
+
+impl<'a> Interrupts<'a> {
+    /// 如果 `buf` 不能切分为每 `cells` 个 cell 一组，返回 `None`。
+    #[inline]
+    pub(crate) fn new(buf: &'a [StructureBlock], cells: u32) -> Option<Self> {
+        if cells != 0 && buf.len().is_multiple_of(cells as _) {
+            Some(Self { buf, cells })
+        } else {
+            None
+        }
+    }
+
+    /// 每个中断说明符的 cell 数，即中断父节点的 `#interrupt-cells`。
+    #[inline]
+    pub fn cells(&self) -> u32 {
+        self.cells
+    }
+}
+
+impl<'a> Iterator for Interrupts<'a> {
+    type Item = RawCells<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buf.is_empty() {
+            return None;
+        }
+        let (head, tail) = self.buf.split_at(self.cells as _);
+        self.buf = tail;
+        Some(RawCells(head))
+    }
+}
+
+impl fmt::Debug for Interrupts<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut iter = self.clone();
+        write!(f, "[")?;
+        if let Some(first) = iter.next() {
+            first.fmt(f)?;
+            for specifier in iter {
+                write!(f, ", ")?;
+                specifier.fmt(f)?;
+            }
+        }
+        write!(f, "]")
+    }
+}
@@ -1,16 +1,27 @@
 ﻿//! §2.3
 
+mod cells;
+mod interrupts;
 mod phandle;
+mod ranges;
 mod reg;
+mod status;
 mod str;
+mod value;
 
 use crate::{Str, StructureBlock};
-use core::{fmt, slice};
+use core::fmt;
 
-pub use self::phandle::PHandle;
+pub use self::cells::RawCells;
+pub use self::interrupts::Interrupts;
+pub use self::phandle::{PHandle, PHandleError};
+pub use self::ranges::{Ranges, RangesEntry};
+pub use self::status::Status;
 pub use self::str::StrList;
-pub use reg::Reg;
+pub use self::value::{PropValue, Tuples};
+pub(crate) use ranges::RangesCfg;
 pub(crate) use reg::RegCfg;
+pub use reg::{Reg, RegEntry};
 
 /// 属性
 pub enum Property<'a> {
@@ -21,19 +32,57 @@ pub enum Property<'a> {
     /// §2.3.3 引用号
     PHandle(PHandle),
     /// §2.3.4 状态
-    Status(Str<'a>),
+    Status(Status<'a>),
+    /// §2.3.4 安全状态
+    SecureStatus(Status<'a>),
+    /// §2.3.5 子节点地址的 cell 数
+    AddressCells(u32),
+    /// §2.3.5 子节点长度的 cell 数
+    SizeCells(u32),
+    /// §2.4.2.1 中断说明符的 cell 数
+    InterruptCells(u32),
+    /// 值不是一个 cell 的 `#address-cells`、`#size-cells` 或 `#interrupt-cells`，不影响解析
+    InvalidCells {
+        /// 属性名
+        name: Str<'a>,
+        /// 属性值
+        value: PropValue<'a>,
+    },
     /// §2.3.6 寄存器
     Reg(Reg<'a>),
     /// §2.3.7 寄存器
     VirtualReg(u32),
+    /// §2.3.8 地址映射
+    Ranges(Ranges<'a>),
+    /// §2.3.9 DMA 地址映射
+    DmaRanges(Ranges<'a>),
     /// §2.3.10 DMA 连贯性
     DmaCoherent,
+    /// §2.3.11 DMA 非连贯性
+    DmaNonCoherent,
+    /// §2.3.12 节点名（已弃用）
+    Name(Str<'a>),
+    /// §2.3.13 设备类型（已弃用）
+    DeviceType(Str<'a>),
+    /// §2.4.1.1 中断
+    Interrupts(Interrupts<'a>),
+    /// §2.4.1.2 中断父节点
+    InterruptParent(PHandle),
+    /// §2.4.2.2 中断控制器
+    InterruptController,
+    /// `clock-names`、`reg-names` 等为另一个属性的各项命名的属性
+    Names {
+        /// 属性名
+        name: Str<'a>,
+        /// 各项的名字
+        names: StrList<'a>,
+    },
     /// 一般属性
     General {
         /// 属性名
         name: Str<'a>,
         /// 属性值
-        value: &'a [u8],
+        value: PropValue<'a>,
     },
 }
 
@@ -45,7 +94,7 @@ impl<'a> Property<'a> {
     pub(crate) fn new(name: &'a [u8], value: &'a [StructureBlock], len: usize) -> Self {
         let general = |Error| Self::General {
             name: Str(name),
-            value: unsafe { slice::from_raw_parts(value.as_ptr().cast(), len) },
+            value: PropValue::new(value, len),
         };
         match name {
             b"compatible" => StrList::new(value, len).map_or_else(general, Self::Compatible),
@@ -53,9 +102,38 @@ impl<'a> Property<'a> {
             b"phandle" | b"linux,phandle" => u32_from(value)
                 .map(PHandle)
                 .map_or_else(general, Self::PHandle),
-            b"status" => Str::new(value, len).map_or_else(general, Self::Status),
+            b"status" => Str::new(value, len)
+                .map(Status::new)
+                .map_or_else(general, Self::Status),
+            b"secure-status" => Str::new(value, len)
+                .map(Status::new)
+                .map_or_else(general, Self::SecureStatus),
+            b"#address-cells" | b"#size-cells" | b"#interrupt-cells" => match u32_from(value) {
+                Ok(cells) if len == 4 => match name {
+                    b"#address-cells" => Self::AddressCells(cells),
+                    b"#size-cells" => Self::SizeCells(cells),
+                    _ => Self::InterruptCells(cells),
+                },
+                _ => Self::InvalidCells {
+                    name: Str(name),
+                    value: PropValue::new(value, len),
+                },
+            },
             b"virtual-reg" => u32_from(value).map_or_else(general, Self::VirtualReg),
             b"dma-coherent" if value.is_empty() => Self::DmaCoherent,
+            b"dma-noncoherent" if value.is_empty() => Self::DmaNonCoherent,
+            b"name" => Str::new(value, len).map_or_else(general, Self::Name),
+            b"device_type" => Str::new(value, len).map_or_else(general, Self::DeviceType),
+            b"interrupt-parent" => u32_from(value)
+                .map(PHandle)
+                .map_or_else(general, Self::InterruptParent),
+            b"interrupt-controller" if value.is_empty() => Self::InterruptController,
+            _ if name.ends_with(b"-names") => {
+                StrList::new(value, len).map_or_else(general, |names| Self::Names {
+                    name: Str(name),
+                    names,
+                })
+            }
             _ => general(Error),
         }
     }
@@ -66,16 +144,33 @@ impl fmt::Debug for Property<'_> {
         match self {
             Self::Compatible(compatible) => write!(f, "compatible = {compatible};"),
             Self::Model(model) => write!(f, "model = {model};"),
-            Self::Reg(reg) => write!(f, "reg = {reg:#x?};"),
+            Self::Reg(reg) => write!(f, "reg = {reg:?};"),
             Self::PHandle(phandle) => write!(f, "phandle = {phandle:?};"),
             Self::Status(status) => write!(f, "status = {status};"),
+            Self::SecureStatus(status) => write!(f, "secure-status = {status};"),
+            Self::AddressCells(cells) => write_cells(f, "#address-cells", cells),
+            Self::SizeCells(cells) => write_cells(f, "#size-cells", cells),
+            Self::InterruptCells(cells) => write_cells(f, "#interrupt-cells", cells),
             Self::VirtualReg(vreg) => {
                 write!(f, "virtual-reg = <")?;
                 vreg.fmt(f)?;
                 write!(f, ">;")
             }
+            Self::Ranges(ranges) if ranges.is_identity() => write!(f, "ranges;"),
+            Self::Ranges(ranges) => write!(f, "ranges = {ranges:?};"),
+            Self::DmaRanges(ranges) if ranges.is_identity() => write!(f, "dma-ranges;"),
+            Self::DmaRanges(ranges) => write!(f, "dma-ranges = {ranges:?};"),
             Self::DmaCoherent => write!(f, "dma-coherent;"),
-            Self::General { name, value } => {
+            Self::DmaNonCoherent => write!(f, "dma-noncoherent;"),
+            Self::Name(name) => write!(f, "name = {name};"),
+            Self::DeviceType(device_type) => write!(f, "device_type = {device_type};"),
+            Self::Interrupts(interrupts) => write!(f, "interrupts = {interrupts:?};"),
+            Self::InterruptParent(phandle) => write!(f, "interrupt-parent = {phandle:?};"),
+            Self::InterruptController => write!(f, "interrupt-controller;"),
+            Self::Names { name, names } => {
+                write!(f, "{} = {names};", unsafe { name.as_str_unchecked() })
+            }
+            Self::InvalidCells { name, value } | Self::General { name, value } => {
                 write!(f, "{}", unsafe { name.as_str_unchecked() })?;
                 match name {
                     _ if !value.is_empty() => {
@@ -90,6 +185,13 @@ impl fmt::Debug for Property<'_> {
     }
 }
 
+#[inline]
+fn write_cells(f: &mut fmt::Formatter<'_>, name: &str, cells: &u32) -> fmt::Result {
+    write!(f, "{name} = <")?;
+    fmt::Debug::fmt(cells, f)?;
+    write!(f, ">;")
+}
+
 #[inline]
 fn u32_from(value: &[StructureBlock]) -> Result<u32> {
     match *value {
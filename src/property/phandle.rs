@@ -1,9 +1,18 @@
 ﻿//! §2.3.3
 
+use crate::StructureError;
 use core::fmt;
 
 /// §2.3.3 phandle 属性
-pub struct PHandle(pub(super) u32);
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PHandle(pub(crate) u32);
+
+impl From<u32> for PHandle {
+    #[inline]
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
 
 impl PHandle {
     /// 返回 phandle 值。
@@ -20,3 +29,19 @@ impl fmt::Debug for PHandle {
         write!(f, ">")
     }
 }
+
+/// 按 phandle 查找节点可能发现的错误类型。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum PHandleError {
+    /// 结构块不合规范。
+    Structure(StructureError),
+    /// 多个节点具有相同的 phandle。
+    Duplicate(PHandle),
+}
+
+impl From<StructureError> for PHandleError {
+    #[inline]
+    fn from(e: StructureError) -> Self {
+        Self::Structure(e)
+    }
+}
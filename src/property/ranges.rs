@@ -0,0 +1,195 @@
+﻿use super::RawCells;
+use crate::StructureBlock;
+use core::fmt;
+
+/// `ranges` 或 `dma-ranges` 属性，描述子总线地址空间到父总线地址空间的映射。
+///
+/// 空的 `ranges;` 表示两个地址空间相同，此时迭代器不产生任何条目。
+#[derive(Clone)]
+pub struct Ranges<'a> {
+    buf: &'a [StructureBlock],
+    cfg: RangesCfg,
+}
+
+/// `ranges` 中的一个映射。
+pub struct RangesEntry<'a> {
+    /// 子总线地址，按节点自身的 `#address-cells` 解析。
+    pub child: RawCells<'a>,
+    /// 父总线地址，按父节点的 `#address-cells` 解析。
+    pub parent: RawCells<'a>,
+    /// 映射的长度，按节点自身的 `#size-cells` 解析。
+    pub size: RawCells<'a>,
+}
+
+impl<'a> RangesEntry<'a> {
+    /// 如果子总线地址在这个映射中，转换为父总线地址的低 64 位。
+    ///
+    /// 超过 2 个 cell 的地址的高位 cell 必须与映射一致；
+    /// 3 个 cell 的 PCI 地址只比较 phys.hi 中的地址空间编码，例如配置空间的地址不会被转换。
+    #[inline]
+    pub fn translate(&self, address: RawCells) -> Option<u64> {
+        Self::map(
+            self.child,
+            self.parent,
+            self.size,
+            address.high(),
+            address.low_u64(),
+        )
+        .map(|(_, address)| address)
+    }
+
+    /// 如果父总线地址在这个映射中，转换为子总线地址的低 64 位。
+    #[inline]
+    pub fn translate_back(&self, address: RawCells) -> Option<u64> {
+        Self::map(
+            self.parent,
+            self.child,
+            self.size,
+            address.high(),
+            address.low_u64(),
+        )
+        .map(|(_, address)| address)
+    }
+
+    /// 将 `from` 空间中的地址映射到 `to` 空间，返回目标地址的高位 cell 和低 64 位。
+    ///
+    /// `high` 为空表示地址只是一个数值，不比较地址空间。
+    fn map(
+        from: RawCells<'a>,
+        to: RawCells<'a>,
+        size: RawCells<'a>,
+        high: RawCells,
+        low: u64,
+    ) -> Option<(RawCells<'a>, u64)> {
+        if !same_space(high, from.high()) {
+            return None;
+        }
+        let offset = low.checked_sub(from.low_u64())?;
+        if offset < size.low_u64() {
+            Some((to.high(), to.low_u64().checked_add(offset)?))
+        } else {
+            None
+        }
+    }
+}
+
+/// PCI 地址 phys.hi 中的地址空间编码。
+const PCI_SPACE_CODE: u32 = 0x0300_0000;
+
+/// 比较两个地址的高位 cell。
+fn same_space(address: RawCells, entry: RawCells) -> bool {
+    match (address.0, entry.0) {
+        ([], _) => true,
+        ([a], [b]) => (a.into_u32() ^ b.into_u32()) & PCI_SPACE_CODE == 0,
+        (a, b) => a == b,
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct RangesCfg {
+    pub child_address_cells: u32,
+    pub parent_address_cells: u32,
+    pub size_cells: u32,
+}
+
+impl RangesCfg {
+    #[inline]
+    fn entry_size(&self) -> usize {
+        (self.child_address_cells as usize)
+            .saturating_add(self.parent_address_cells as _)
+            .saturating_add(self.size_cells as _)
+    }
+}
+
+impl<'a> Ranges<'a> {
+    #[inline]
+    pub(crate) fn new(buf: &'a [StructureBlock], cfg: RangesCfg) -> Option<Self> {
+        match cfg.entry_size() {
+            _ if buf.is_empty() => Some(Self { buf, cfg }),
+            0 => None,
+            n if buf.len().is_multiple_of(n) => Some(Self { buf, cfg }),
+            _ => None,
+        }
+    }
+
+    /// 如果这是空的 `ranges;`，即恒等映射，则返回 `true`。
+    #[inline]
+    pub fn is_identity(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    /// 将子总线地址转换为父总线地址的低 64 位。如果地址不在任何映射中则返回 `None`。
+    ///
+    /// 地址的比较方式见 [`RangesEntry::translate`]。
+    #[inline]
+    pub fn translate(&self, address: RawCells) -> Option<u64> {
+        self.map(false, address.high(), address.low_u64())
+            .map(|(_, address)| address)
+    }
+
+    /// 将父总线地址转换为子总线地址的低 64 位。如果地址不在任何映射中则返回 `None`。
+    #[inline]
+    pub fn translate_back(&self, address: RawCells) -> Option<u64> {
+        self.map(true, address.high(), address.low_u64())
+            .map(|(_, address)| address)
+    }
+
+    /// 转换由高位 cell 和低 64 位组成的地址，`back` 表示从父总线转换到子总线。
+    pub(crate) fn map(
+        &self,
+        back: bool,
+        high: RawCells<'a>,
+        low: u64,
+    ) -> Option<(RawCells<'a>, u64)> {
+        if self.is_identity() {
+            return Some((high, low));
+        }
+        self.clone().find_map(|entry| {
+            if back {
+                RangesEntry::map(entry.parent, entry.child, entry.size, high, low)
+            } else {
+                RangesEntry::map(entry.child, entry.parent, entry.size, high, low)
+            }
+        })
+    }
+}
+
+impl<'a> Iterator for Ranges<'a> {
+    type Item = RangesEntry<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buf.is_empty() {
+            return None;
+        }
+        let (child, tail) = self.buf.split_at(self.cfg.child_address_cells as _);
+        let (parent, tail) = tail.split_at(self.cfg.parent_address_cells as _);
+        let (size, tail) = tail.split_at(self.cfg.size_cells as _);
+        self.buf = tail;
+        Some(RangesEntry {
+            child: RawCells(child),
+            parent: RawCells(parent),
+            size: RawCells(size),
+        })
+    }
+}
+
+impl fmt::Debug for RangesEntry<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} {:?} {:?}", self.child, self.parent, self.size)
+    }
+}
+
+impl fmt::Debug for Ranges<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut iter = self.clone();
+        write!(f, "[")?;
+        if let Some(first) = iter.next() {
+            first.fmt(f)?;
+            for entry in iter {
+                write!(f, ", ")?;
+                entry.fmt(f)?;
+            }
+        }
+        write!(f, "]")
+    }
+}
@@ -1,4 +1,5 @@
-﻿use crate::StructureBlock;
+﻿use super::RawCells;
+use crate::StructureBlock;
 use core::{fmt, ops::Range};
 
 /// `reg` 属性。
@@ -8,8 +9,54 @@ pub struct Reg<'a> {
     pub(crate) cfg: RegCfg,
 }
 
-impl Iterator for Reg<'_> {
-    type Item = Range<usize>;
+/// `reg` 中的一项。
+#[derive(Clone, Copy)]
+pub struct RegEntry<'a> {
+    address: RawCells<'a>,
+    size: RawCells<'a>,
+}
+
+impl<'a> RegEntry<'a> {
+    /// 地址的所有 cell。
+    ///
+    /// 3 个 cell 的 PCI 地址的第一个 cell 描述地址空间，地址本身可以用 [`RawCells::low_u64`] 取得。
+    #[inline]
+    pub fn raw_address(&self) -> RawCells<'a> {
+        self.address
+    }
+
+    /// 长度的所有 cell。`#size-cells` 为 0 时为空。
+    #[inline]
+    pub fn raw_size(&self) -> RawCells<'a> {
+        self.size
+    }
+
+    /// 地址。超出 `u64` 的范围时返回 `None`。
+    #[inline]
+    pub fn address(&self) -> Option<u64> {
+        self.address.to_u64()
+    }
+
+    /// 长度。`#size-cells` 为 0 或超出 `u64` 的范围时返回 `None`。
+    #[inline]
+    pub fn size(&self) -> Option<u64> {
+        if self.size.is_empty() {
+            None
+        } else {
+            self.size.to_u64()
+        }
+    }
+
+    /// 地址区间。没有长度或区间超出 `u64` 的范围时返回 `None`。
+    #[inline]
+    pub fn range(&self) -> Option<Range<u64>> {
+        let address = self.address()?;
+        Some(address..address.checked_add(self.size()?)?)
+    }
+}
+
+impl<'a> Iterator for Reg<'a> {
+    type Item = RegEntry<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.buf.is_empty() {
@@ -19,17 +66,23 @@ impl Iterator for Reg<'_> {
             address_cells,
             size_cells,
         } = self.cfg;
-        let (base_buf, tail) = self.buf.split_at(address_cells as _);
-        let (size_buf, tail) = tail.split_at(size_cells as _);
+        let (address, tail) = self.buf.split_at(address_cells as _);
+        let (size, tail) = tail.split_at(size_cells as _);
         self.buf = tail;
+        Some(RegEntry {
+            address: RawCells(address),
+            size: RawCells(size),
+        })
+    }
+}
 
-        let base = base_buf
-            .iter()
-            .fold(0usize, |acc, it| (acc << 32) + it.into_u32() as usize);
-        let size = size_buf
-            .iter()
-            .fold(0usize, |acc, it| (acc << 32) + it.into_u32() as usize);
-        Some(base..base + size)
+impl fmt::Debug for RegEntry<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.size.is_empty() {
+            write!(f, "{:?}", self.address)
+        } else {
+            write!(f, "{:?} {:?}", self.address, self.size)
+        }
     }
 }
 
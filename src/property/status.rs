@@ -0,0 +1,82 @@
+﻿//! §2.3.4
+
+use crate::Str;
+use core::fmt;
+
+/// 节点状态，来自 `status` 或 `secure-status` 属性。
+///
+/// 没有状态属性的节点视作 [`Status::Okay`]。
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Status<'a> {
+    /// 设备可以使用。旧的 `"ok"` 也视作此状态。
+    Okay,
+    /// 设备目前不可用，但将来可能变为可用，例如设备未插入或关闭。
+    Disabled,
+    /// 设备可以使用，但不应使用，通常表示设备由其他软件控制。
+    Reserved,
+    /// 设备出错，不可用，不太可能恢复。
+    Fail,
+    /// 设备出错，不可用。值是设备相关的错误条件。
+    FailSss(Str<'a>),
+    /// 不合规范的状态。
+    Unknown(Str<'a>),
+}
+
+impl<'a> Status<'a> {
+    /// 解析状态字符串。
+    pub fn new(s: Str<'a>) -> Self {
+        match s.0 {
+            b"okay" | b"ok" => Self::Okay,
+            b"disabled" => Self::Disabled,
+            b"reserved" => Self::Reserved,
+            b"fail" => Self::Fail,
+            [b'f', b'a', b'i', b'l', b'-', sss @ ..] => Self::FailSss(Str(sss)),
+            _ => Self::Unknown(s),
+        }
+    }
+
+    /// 如果设备可以使用则返回 `true`。
+    #[inline]
+    pub fn is_available(&self) -> bool {
+        matches!(self, Self::Okay)
+    }
+}
+
+impl Default for Status<'_> {
+    #[inline]
+    fn default() -> Self {
+        Self::Okay
+    }
+}
+
+impl fmt::Display for Status<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Okay => write!(f, "okay"),
+            Self::Disabled => write!(f, "disabled"),
+            Self::Reserved => write!(f, "reserved"),
+            Self::Fail => write!(f, "fail"),
+            Self::FailSss(sss) => write!(f, "fail-{sss}"),
+            Self::Unknown(s) => s.fmt(f),
+        }
+    }
+}
+
+impl fmt::Debug for Status<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Okay => write!(f, "Okay"),
+            Self::Disabled => write!(f, "Disabled"),
+            Self::Reserved => write!(f, "Reserved"),
+            Self::Fail => write!(f, "Fail"),
+            Self::FailSss(sss) => f
+                .debug_tuple("FailSss")
+                .field(&unsafe { sss.as_str_unchecked() })
+                .finish(),
+            Self::Unknown(s) => f
+                .debug_tuple("Unknown")
+                .field(&unsafe { s.as_str_unchecked() })
+                .finish(),
+        }
+    }
+}
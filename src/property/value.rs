@@ -0,0 +1,108 @@
+﻿//! see §2.2.4/Property-Values
+
+use super::{RawCells, StrList};
+use crate::{Str, StructureBlock};
+use core::{fmt, slice};
+
+/// 未知类型的属性值。
+///
+/// 所有解析都检查长度和格式，不合规时返回 `None`。
+#[derive(Clone, Copy)]
+pub struct PropValue<'a> {
+    buf: &'a [StructureBlock],
+    len: usize,
+}
+
+impl<'a> PropValue<'a> {
+    #[inline]
+    pub(crate) fn new(buf: &'a [StructureBlock], len: usize) -> Self {
+        Self { buf, len }
+    }
+
+    /// 属性值的字节切片。
+    #[inline]
+    pub fn as_bytes(&self) -> &'a [u8] {
+        unsafe { slice::from_raw_parts(self.buf.as_ptr().cast(), self.len) }
+    }
+
+    /// 如果属性值为空则返回 `true`，即 `<empty>` 类型的布尔属性。
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 解析 `<u32>` 类型的属性值。
+    #[inline]
+    pub fn as_u32(&self) -> Option<u32> {
+        match *self.buf {
+            [x] if self.len == 4 => Some(x.into_u32()),
+            _ => None,
+        }
+    }
+
+    /// 解析 1 或 2 个 cell 的整数，例如可以是 `<u32>` 或 `<u64>` 的 `clock-frequency`。
+    #[inline]
+    pub fn as_u64(&self) -> Option<u64> {
+        match self.as_cells()? {
+            cells if (1..=2).contains(&cells.len()) => cells.to_u64(),
+            _ => None,
+        }
+    }
+
+    /// 将属性值视作 cell 序列。长度不是 4 的整数倍时返回 `None`。
+    #[inline]
+    pub fn as_cells(&self) -> Option<RawCells<'a>> {
+        if self.len.is_multiple_of(StructureBlock::LEN) {
+            Some(RawCells(self.buf))
+        } else {
+            None
+        }
+    }
+
+    /// 解析 `<string>` 类型的属性值，即一个以 `'\0'` 结尾的字符串。
+    #[inline]
+    pub fn as_str(&self) -> Option<Str<'a>> {
+        match self.as_bytes().split_last() {
+            Some((b'\0', s)) if !s.contains(&b'\0') => Some(Str(s)),
+            _ => None,
+        }
+    }
+
+    /// 解析 `<stringlist>` 类型的属性值。
+    #[inline]
+    pub fn as_str_list(&self) -> Option<StrList<'a>> {
+        StrList::new(self.buf, self.len).ok()
+    }
+
+    /// 将 `<prop-encoded-array>` 类型的属性值按每 `N` 个 cell 一组切分。
+    ///
+    /// cell 的数量不是 `N` 的整数倍或 `N` 为 0 时返回 `None`。
+    #[inline]
+    pub fn as_tuples<const N: usize>(&self) -> Option<Tuples<'a, N>> {
+        match self.as_cells()? {
+            cells if N != 0 && cells.len().is_multiple_of(N) => Some(Tuples(cells.0)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Debug for PropValue<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_bytes().fmt(f)
+    }
+}
+
+/// 每 `N` 个 cell 一组的属性值迭代器。
+#[derive(Clone)]
+pub struct Tuples<'a, const N: usize>(&'a [StructureBlock]);
+
+impl<const N: usize> Iterator for Tuples<'_, N> {
+    type Item = [u32; N];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (head, tail) = self.0.split_at_checked(N)?;
+        self.0 = tail;
+        Some(core::array::from_fn(|i| head[i].into_u32()))
+    }
+}
@@ -0,0 +1,131 @@
+﻿//! §3.5
+
+use crate::{Children, Node, PHandle, Property, Reg, RegCfg, Status, StrList, StructureError};
+
+/// `/reserved-memory` 节点中的一个保留区。
+///
+/// 地址和长度按 `/reserved-memory` 节点的 `#address-cells` 和 `#size-cells` 解析。
+pub struct ReservedRegion<'a> {
+    /// 描述保留区的节点。
+    pub node: Node<'a>,
+    /// `reg`，静态保留区的地址。
+    pub reg: Option<Reg<'a>>,
+    /// `size`，动态保留区的长度。
+    pub size: Option<u64>,
+    /// `alignment`，动态保留区的对齐。
+    pub alignment: Option<u64>,
+    /// `alloc-ranges`，动态保留区可以分配的地址范围。
+    pub alloc_ranges: Option<Reg<'a>>,
+    /// `no-map`，操作系统不能映射这个保留区。
+    pub no_map: bool,
+    /// `reusable`，操作系统可以使用这个保留区，只要驱动程序能够收回它。
+    pub reusable: bool,
+    /// `compatible`，例如 `shared-dma-pool`。
+    pub compatible: Option<StrList<'a>>,
+    /// `phandle`，其他节点通过 `memory-region` 引用这个保留区。
+    pub phandle: Option<PHandle>,
+    /// `status`，不可用的保留区应该被忽略。
+    pub status: Status<'a>,
+}
+
+impl<'a> ReservedRegion<'a> {
+    /// 如果保留区是动态分配的则返回 `true`。
+    #[inline]
+    pub fn is_dynamic(&self) -> bool {
+        self.reg.is_none() && self.size.is_some()
+    }
+
+    /// 从 `/reserved-memory` 的子节点解析。不合规范的属性被忽略。
+    fn new(node: Node<'a>) -> Result<Self, StructureError> {
+        let cells = node.parent_cells();
+        let cfg = RegCfg {
+            address_cells: cells.address,
+            size_cells: cells.size,
+        };
+        let mut ans = Self {
+            node: node.clone(),
+            reg: None,
+            size: None,
+            alignment: None,
+            alloc_ranges: None,
+            no_map: false,
+            reusable: false,
+            compatible: None,
+            phandle: None,
+            status: node.status()?,
+        };
+        for prop in node.raw_properties() {
+            let prop = prop?;
+            match prop.name {
+                b"reg" | b"alloc-ranges"
+                    if cells.reg_size() != 0
+                        && !prop.buf.is_empty()
+                        && prop.buf.len().is_multiple_of(cells.reg_size()) =>
+                {
+                    let reg = Some(Reg { buf: prop.buf, cfg });
+                    if prop.name == b"reg" {
+                        ans.reg = reg;
+                    } else {
+                        ans.alloc_ranges = reg;
+                    }
+                }
+                b"size" if prop.buf.len() == cells.size as usize => {
+                    ans.size = prop.value().as_u64()
+                }
+                b"alignment" if prop.buf.len() == cells.size as usize => {
+                    ans.alignment = prop.value().as_u64()
+                }
+                b"no-map" => ans.no_map = true,
+                b"reusable" => ans.reusable = true,
+                _ => match Property::new(prop.name, prop.buf, prop.len) {
+                    Property::Compatible(compatible) => ans.compatible = Some(compatible),
+                    Property::PHandle(phandle) => ans.phandle = Some(phandle),
+                    _ => {}
+                },
+            }
+        }
+        Ok(ans)
+    }
+}
+
+/// 保留区迭代器。
+pub struct ReservedMemory<'a> {
+    children: Option<Children<'a>>,
+    /// 查找 `/reserved-memory` 节点时发现的错误。
+    pending: Option<StructureError>,
+}
+
+impl<'a> ReservedMemory<'a> {
+    #[inline]
+    pub(crate) fn new(children: Result<Option<Children<'a>>, StructureError>) -> Self {
+        match children {
+            Ok(children) => Self {
+                children,
+                pending: None,
+            },
+            Err(e) => Self {
+                children: None,
+                pending: Some(e),
+            },
+        }
+    }
+}
+
+impl<'a> Iterator for ReservedMemory<'a> {
+    type Item = Result<ReservedRegion<'a>, StructureError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.pending.take() {
+            return Some(Err(e));
+        }
+        let ans = self
+            .children
+            .as_mut()?
+            .next()?
+            .and_then(ReservedRegion::new);
+        if ans.is_err() {
+            self.children = None;
+        }
+        Some(ans)
+    }
+}
@@ -0,0 +1,153 @@
+﻿//! §2.4.2 §6
+
+use crate::{Node, PHandle, RawCells, StructureBlock as Blk, StructureError};
+
+impl<'a> Node<'a> {
+    /// 遍历 `<&provider args...>` 形式的属性 `name`，例如 `clocks`、`resets`、`gpios`。
+    ///
+    /// 每一项的参数数量由引用的节点的 `cells_name` 属性决定，例如 `#clock-cells`。
+    /// 引用号为 0 的项是占位符，没有参数。如果节点没有这个属性，迭代器为空。
+    #[inline]
+    pub fn phandle_args<'c>(&self, name: &str, cells_name: &'c str) -> PHandleArgs<'a, 'c> {
+        self.phandle_args_with(name, cells_name, true)
+    }
+
+    /// 遍历 `<&provider args...>` 形式的属性 `name`。
+    ///
+    /// `placeholder` 为 `false` 时引用号为 0 的项报告为 [`SpecifierError::Dangling`]。
+    pub(crate) fn phandle_args_with<'c>(
+        &self,
+        name: &str,
+        cells_name: &'c str,
+        placeholder: bool,
+    ) -> PHandleArgs<'a, 'c> {
+        let mut args = PHandleArgs {
+            node: self.clone(),
+            buf: &[],
+            cells_name,
+            placeholder,
+            pending: None,
+        };
+        for prop in self.raw_properties() {
+            match prop {
+                Ok(prop) if prop.name == name.as_bytes() => {
+                    args.buf = prop.buf;
+                    break;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    args.pending = Some(e.into());
+                    break;
+                }
+            }
+        }
+        args
+    }
+
+    /// 按名字查找 `<&provider args...>` 形式的属性 `name` 中的一项。
+    ///
+    /// 名字来自字符串列表属性 `names`，例如 `clocks` 对应 `clock-names`，第 n 个名字对应第 n 项。
+    /// 如果 `names` 不是合规的字符串列表，返回 [`SpecifierError::InvalidNames`]。
+    pub fn phandle_args_by_name(
+        &self,
+        name: &str,
+        cells_name: &str,
+        names: &str,
+        entry: &str,
+    ) -> Result<Option<(PHandle, RawCells<'a>)>, SpecifierError> {
+        let mut index = None;
+        for prop in self.raw_properties() {
+            let prop = prop?;
+            if prop.name == names.as_bytes() {
+                index = prop
+                    .value()
+                    .as_str_list()
+                    .ok_or(SpecifierError::InvalidNames)?
+                    .position(|s| s.as_bytes() == entry.as_bytes());
+                break;
+            }
+        }
+        match index {
+            Some(i) => self.phandle_args(name, cells_name).nth(i).transpose(),
+            None => Ok(None),
+        }
+    }
+}
+
+/// 解析 `<&provider args...>` 形式的属性可能发现的错误类型。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SpecifierError {
+    /// 结构块不合规范。
+    Structure(StructureError),
+    /// 没有节点具有这个引用号。
+    Dangling(PHandle),
+    /// 引用的节点没有描述参数数量的属性。
+    MissingCells(PHandle),
+    /// 属性值在这一项的参数中间结束。
+    Truncated(PHandle),
+    /// 命名各项的属性不是合规的字符串列表。
+    InvalidNames,
+}
+
+impl From<StructureError> for SpecifierError {
+    #[inline]
+    fn from(e: StructureError) -> Self {
+        Self::Structure(e)
+    }
+}
+
+/// `<&provider args...>` 形式的属性迭代器。
+pub struct PHandleArgs<'a, 'c> {
+    node: Node<'a>,
+    buf: &'a [Blk],
+    /// 描述参数数量的属性名。
+    cells_name: &'c str,
+    /// 引用号为 0 的项是否是占位符。
+    placeholder: bool,
+    /// 查找属性时发现的错误。
+    pending: Option<SpecifierError>,
+}
+
+impl<'a> PHandleArgs<'a, '_> {
+    fn next_entry(&mut self) -> Result<Option<(PHandle, RawCells<'a>)>, SpecifierError> {
+        let Some((phandle, tail)) = self.buf.split_first() else {
+            return Ok(None);
+        };
+        let phandle = PHandle(phandle.into_u32());
+        // 占位符
+        if phandle.0 == 0 && self.placeholder {
+            self.buf = tail;
+            return Ok(Some((phandle, RawCells(&[]))));
+        }
+        let provider = self
+            .node
+            .find_phandle(phandle)?
+            .ok_or(SpecifierError::Dangling(phandle))?;
+        let cells = provider
+            .raw_u32(self.cells_name.as_bytes())?
+            .ok_or(SpecifierError::MissingCells(phandle))? as usize;
+        let Some((args, tail)) = tail.split_at_checked(cells) else {
+            return Err(SpecifierError::Truncated(phandle));
+        };
+        self.buf = tail;
+        Ok(Some((phandle, RawCells(args))))
+    }
+}
+
+impl<'a> Iterator for PHandleArgs<'a, '_> {
+    type Item = Result<(PHandle, RawCells<'a>), SpecifierError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.pending.take() {
+            self.buf = &[];
+            return Some(Err(e));
+        }
+        match self.next_entry() {
+            Ok(entry) => entry.map(Ok),
+            Err(e) => {
+                self.buf = &[];
+                Some(Err(e))
+            }
+        }
+    }
+}
@@ -49,3 +49,55 @@ impl StructureBlock {
         }
     }
 }
+
+/// 结构块解析可能发现的错误类型。
+///
+/// 偏移均为相对结构块起始的字节偏移。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum StructureError {
+    /// 结构块或字符串区超出设备树范围。
+    OutOfBounds,
+    /// 结构块不对齐。
+    Misaligned,
+    /// 未知的标记。
+    UnknownToken {
+        /// 标记值。
+        token: u32,
+        /// 标记的偏移。
+        offset: usize,
+    },
+    /// 节点名没有 '\0' 结尾。
+    UnterminatedNodeName {
+        /// 节点开始标记的偏移。
+        offset: usize,
+    },
+    /// 属性名没有 '\0' 结尾。
+    UnterminatedPropName {
+        /// 属性标记的偏移。
+        offset: usize,
+    },
+    /// 属性名偏移超出字符串区。
+    NameOffset {
+        /// 属性名在字符串区中的偏移。
+        nameoff: u32,
+        /// 属性标记的偏移。
+        offset: usize,
+    },
+    /// 属性长度超出结构块。
+    PropertyOverrun {
+        /// 属性标记的偏移。
+        offset: usize,
+    },
+    /// 节点开始和结束标记不配对。
+    Unbalanced {
+        /// 不配对的标记的偏移。
+        offset: usize,
+    },
+    /// 结构块不以 FDT_END 结尾。
+    MissingEnd,
+    /// 节点嵌套超过 [`Events::MAX_DEPTH`](crate::Events::MAX_DEPTH)。遍历和构造上下文都受此限制。
+    TooDeep {
+        /// 节点开始标记的偏移。
+        offset: usize,
+    },
+}
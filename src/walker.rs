@@ -1,113 +1,382 @@
 ﻿use crate::{
-    context::Cells, Context, DtbObj, Property, Reg, RegCfg, Str, StructureBlock as Blk,
-    WalkOperation,
+    context::{Bus, Cells, InterruptParent},
+    is_aligned, Context, DtbObj, Events, Interrupts, Node, PHandle, Property, Ranges, RangesCfg,
+    Reg, RegCfg, Str, StructureBlock as Blk, StructureError, WalkOperation,
 };
 
 /// 设备树递归结构。
+#[derive(Clone)]
 pub(crate) struct Walker<'a> {
+    /// 整个结构块。
+    pub base: &'a [Blk],
+    /// 尚未解析的结构块。
     pub tail: &'a [Blk],
+    /// 字符串区。
     pub strings: &'a [u8],
+    /// v16 之前的结构块格式。
+    pub legacy: bool,
 }
 
-impl Walker<'_> {
+/// 结构块中的一个标记。
+pub(crate) enum Token<'a> {
+    /// FDT_BEGIN_NODE 及节点名。
+    Begin(Str<'a>),
+    /// FDT_END_NODE
+    End,
+    /// FDT_PROP 及属性名和属性值。
+    Prop {
+        name: &'a [u8],
+        value: &'a [Blk],
+        len: usize,
+    },
+    /// FDT_END
+    Finish,
+}
+
+impl<'a> Walker<'a> {
+    /// 构造一个从结构块起点开始解析的游标。
+    #[inline]
+    pub fn new(base: &'a [Blk], strings: &'a [u8], legacy: bool) -> Self {
+        Self {
+            base,
+            tail: base,
+            strings,
+            legacy,
+        }
+    }
+
+    /// 下一个标记相对结构块起始的字节偏移，不计 FDT_NOP。
+    #[inline]
+    pub fn offset(&self) -> usize {
+        let nop = self.tail.iter().take_while(|blk| **blk == Blk::NOP).count();
+        (self.base.len() - self.tail.len() + nop) * Blk::LEN
+    }
+
+    /// 构造一个从结构块起点开始解析的新游标。
+    #[inline]
+    pub fn rewind(&self) -> Self {
+        Self::new(self.base, self.strings, self.legacy)
+    }
+
     /// 切分属性名。
-    fn prop_name(&self, nameoff: Blk) -> &[u8] {
-        let nameoff = nameoff.into_u32() as usize;
-        let name = &self.strings[nameoff..];
-        &name[..name.iter().position(|c| *c == b'\0').unwrap()]
+    fn prop_name(&self, nameoff: Blk, offset: usize) -> Result<&'a [u8], StructureError> {
+        let strings = self.strings;
+        let nameoff = nameoff.into_u32();
+        let name = strings
+            .get(nameoff as usize..)
+            .ok_or(StructureError::NameOffset { nameoff, offset })?;
+        match name.iter().position(|c| *c == b'\0') {
+            Some(len) => Ok(&name[..len]),
+            None => Err(StructureError::UnterminatedPropName { offset }),
+        }
+    }
+
+    /// 解析下一个标记，跳过所有 FDT_NOP。
+    pub fn next_token(&mut self) -> Result<Token<'a>, StructureError> {
+        loop {
+            let offset = self.offset();
+            match self.tail.split_first() {
+                // 子节点
+                Some((&Blk::NODE_BEGIN, tail)) => {
+                    // 找到字符串结尾
+                    let name_len = tail
+                        .iter()
+                        .position(Blk::is_end_of_str)
+                        .ok_or(StructureError::UnterminatedNodeName { offset })?;
+                    let (name, tail) = tail.split_at(name_len + 1);
+                    self.tail = tail;
+                    // 正确舍弃尾 '\0'
+                    let name = unsafe {
+                        core::slice::from_raw_parts(
+                            name.as_ptr().cast::<u8>(),
+                            name.len() * Blk::LEN - name[name_len].str_tail_zero(),
+                        )
+                    };
+                    // 旧版本的节点名是完整路径，只保留最后一级
+                    let name = match name.iter().rposition(|c| *c == b'/') {
+                        Some(i) if self.legacy => &name[i + 1..],
+                        _ => name,
+                    };
+                    return Ok(Token::Begin(Str(name)));
+                }
+                // 当前节点结束
+                Some((&Blk::NODE_END, tail)) => {
+                    self.tail = tail;
+                    return Ok(Token::End);
+                }
+                // 属性
+                Some((&Blk::PROP, [len, nameoff, tail @ ..])) => {
+                    let len = len.into_u32() as usize;
+                    // 旧版本长度不小于 8 的属性值按 8 字节对齐
+                    let tail = match tail {
+                        [_, tail @ ..]
+                            if self.legacy && len >= 8 && !is_aligned(offset + 3 * Blk::LEN, 8) =>
+                        {
+                            tail
+                        }
+                        _ => tail,
+                    };
+                    // 切分属性值
+                    let n = len.div_ceil(Blk::LEN);
+                    if n > tail.len() {
+                        return Err(StructureError::PropertyOverrun { offset });
+                    }
+                    let name = self.prop_name(*nameoff, offset)?;
+                    let (value, tail) = tail.split_at(n);
+                    self.tail = tail;
+                    return Ok(Token::Prop { name, value, len });
+                }
+                Some((&Blk::PROP, _)) => return Err(StructureError::PropertyOverrun { offset }),
+                // 跳过
+                Some((&Blk::NOP, tail)) => self.tail = tail,
+                Some((&Blk::END, tail)) => {
+                    self.tail = tail;
+                    return Ok(Token::Finish);
+                }
+                Some((token, _)) => {
+                    return Err(StructureError::UnknownToken {
+                        token: token.into_u32(),
+                        offset,
+                    })
+                }
+                None => return Err(StructureError::MissingEnd),
+            }
+        }
+    }
+
+    /// 读取当前节点剩余的属性，更新节点自身的 cells。
+    ///
+    /// `ranges` 等属性的解析依赖节点自身的 `#address-cells`，但它们可能出现在其后。
+    pub fn lookahead_cells(&self, mut cells: Cells) -> Cells {
+        let mut walker = self.clone();
+        while let Ok(Token::Prop { name, value, len }) = walker.next_token() {
+            cells.absorb(name, value, len);
+        }
+        cells
+    }
+
+    /// 找到当前节点的中断父节点。游标应位于节点的属性之前。
+    ///
+    /// 节点没有 `interrupt-parent` 时使用 `inherited`，即设备树中的父节点为子节点提供的中断父节点。
+    /// `cache` 记录上一次按引用号找到的中断父节点，兄弟节点通常指向同一个。
+    /// 引用号悬空或找不到 `#interrupt-cells` 时返回 `None`。
+    pub fn interrupt_parent(
+        &self,
+        inherited: Option<InterruptParent>,
+        cache: &mut Option<InterruptParent>,
+    ) -> Result<Option<InterruptParent>, StructureError> {
+        let mut walker = self.clone();
+        let phandle = loop {
+            match walker.next_token() {
+                Ok(Token::Prop {
+                    name: b"interrupt-parent",
+                    value,
+                    len,
+                }) => match (value, len) {
+                    ([value], 4) => break PHandle(value.into_u32()),
+                    _ => return Ok(inherited),
+                },
+                Ok(Token::Prop { .. }) => {}
+                _ => return Ok(inherited),
+            }
+        };
+        if let Some(known) = [inherited, *cache]
+            .into_iter()
+            .flatten()
+            .find(|known| known.phandle == Some(phandle))
+        {
+            return Ok(Some(known));
+        }
+        let cells = match Node::root(self.rewind())?.find_phandle(phandle)? {
+            Some(node) => match node.interrupt_cells()? {
+                Some(cells) => Some(cells),
+                // 引用的节点不是中断控制器，从它继续寻找
+                None => match node.interrupt_parent()? {
+                    Some(parent) => parent.interrupt_cells()?,
+                    None => None,
+                },
+            },
+            None => None,
+        };
+        let found = cells.map(|cells| InterruptParent {
+            phandle: Some(phandle),
+            cells,
+        });
+        if found.is_some() {
+            *cache = found;
+        }
+        Ok(found)
+    }
+
+    /// 跳过当前节点剩余的部分，包括其结束标记。
+    pub fn skip_node(&mut self) -> Result<(), StructureError> {
+        let mut level = 0usize;
+        loop {
+            let offset = self.offset();
+            match self.next_token()? {
+                Token::Begin(_) => level += 1,
+                Token::End if level == 0 => return Ok(()),
+                Token::End => level -= 1,
+                Token::Prop { .. } => {}
+                Token::Finish => return Err(StructureError::Unbalanced { offset }),
+            }
+        }
+    }
+
+    /// 从根节点开始深度优先遍历整个结构块。
+    ///
+    /// 如果 `available` 为 `true`，不可用的节点及其子树不会交给 `f`。
+    pub fn walk_root(
+        &mut self,
+        f: &mut impl FnMut(&Context<'_>, DtbObj) -> WalkOperation,
+        available: bool,
+    ) -> Result<(), StructureError> {
+        let offset = self.offset();
+        if !matches!(self.next_token()?, Token::Begin(_)) {
+            return Err(StructureError::Unbalanced { offset });
+        }
+        let ctx = Context::root(self.interrupt_parent(None, &mut None)?);
+        if self.walk_inner(f, Some(ctx), available, 1)? {
+            let offset = self.offset();
+            if !matches!(self.next_token()?, Token::Finish) {
+                return Err(StructureError::Unbalanced { offset });
+            }
+        }
+        Ok(())
     }
 
     /// 深度优先遍历。如果返回 `false`，取消所有后续的遍历。
+    ///
+    /// `depth` 是打开的节点数量，超过 [`Events::MAX_DEPTH`] 时报告错误而不是继续递归。
     pub fn walk_inner(
         &mut self,
         f: &mut impl FnMut(&Context<'_>, DtbObj) -> WalkOperation,
         mut ctx: Option<Context>,
-    ) -> bool {
+        available: bool,
+        depth: usize,
+    ) -> Result<bool, StructureError> {
         use WalkOperation::*;
 
         let mut cells = Cells::DEFAULT;
+        let mut bus = match &ctx {
+            Some(ctx) => Bus::new(ctx.bus().dma_coherent, ctx.interrupt_parent()),
+            None => Bus::new(false, None),
+        };
+        // 子节点按引用号找到的中断父节点
+        let mut resolved = None;
         loop {
-            match self.tail.split_first() {
+            let offset = self.offset();
+            match self.next_token()? {
                 // 子节点
-                Some((&Blk::NODE_BEGIN, tail)) => {
-                    // 找到字符串结尾
-                    let name_len = tail.iter().position(Blk::is_end_of_str).unwrap() + 1;
-                    let (name, tail) = tail.split_at(name_len);
-                    self.tail = tail;
+                Token::Begin(name) => {
+                    if depth == Events::MAX_DEPTH {
+                        return Err(StructureError::TooDeep { offset });
+                    }
+                    // 节点状态在节点名之后才出现，需要预读
+                    if available
+                        && ctx.is_some()
+                        && !Node::new(self.clone(), name, offset, cells)
+                            .status()?
+                            .is_available()
+                    {
+                        self.skip_node()?;
+                        continue;
+                    }
                     if let Some(ctx_) = ctx.as_ref() {
-                        // 正确舍弃尾 '\0'
-                        let name = Str(unsafe {
-                            core::slice::from_raw_parts(
-                                name.as_ptr().cast::<u8>(),
-                                name.len() * Blk::LEN - name.last().unwrap().str_tail_zero(),
-                            )
-                        });
                         let ctx = match f(ctx_, DtbObj::SubNode { name }) {
-                            StepInto => Some(ctx_.grow(name, cells)),
+                            StepInto => {
+                                let interrupt_parent =
+                                    self.interrupt_parent(bus.interrupt_parent, &mut resolved)?;
+                                Some(ctx_.grow(name, cells, bus.clone(), interrupt_parent))
+                            }
                             StepOver => None,
                             StepOut => {
                                 ctx = None;
                                 None
                             }
-                            Terminate => return false,
+                            Terminate => return Ok(false),
                         };
-                        if !self.walk_inner(f, ctx) {
-                            return false;
+                        if !self.walk_inner(f, ctx, available, depth + 1)? {
+                            return Ok(false);
                         }
                     } else {
                         // 如果当前子树已选跳过，不可能再选择终止
-                        assert!(self.walk_inner(f, None));
+                        self.walk_inner(f, None, available, depth + 1)?;
                     }
                 }
                 // 当前节点结束
-                Some((&Blk::NODE_END, tail)) => {
-                    self.tail = tail;
-                    return true;
-                }
+                Token::End => return Ok(true),
                 // 属性
-                Some((&Blk::PROP, [len, nameoff, tail @ ..])) => {
-                    // 切分属性值
-                    let len = len.into_u32() as usize;
-                    let (value, tail) = tail.split_at((len + Blk::LEN - 1) / Blk::LEN);
+                Token::Prop { name, value, len } => {
                     // 如果当前子树需要解析
                     if let Some(ctx_) = ctx.as_ref() {
-                        let op = match self.prop_name(*nameoff) {
-                            b"#address-cells" if value.len() == 1 => {
-                                cells.address = value[0].into_u32();
-                                StepOver
-                            }
-                            b"#size-cells" if value.len() == 1 => {
-                                cells.size = value[0].into_u32();
-                                StepOver
-                            }
-                            b"#interrupt-cells" if value.len() == 1 => {
-                                cells.interrupt = value[0].into_u32();
-                                StepOver
-                            }
-                            b"reg" if value.len() % (ctx_.cells().reg_size()) == 0 => f(
-                                ctx_,
-                                DtbObj::Property(Property::Reg(Reg {
-                                    buf: value,
-                                    cfg: RegCfg {
-                                        address_cells: ctx_.cells().address,
-                                        size_cells: ctx_.cells().size,
-                                    },
-                                })),
-                            ),
-                            name => f(ctx_, DtbObj::Property(Property::new(name, value, len))),
-                        };
-                        match op {
+                        let interrupt_cells = ctx_.interrupt_parent().map(|p| p.cells);
+                        let prop = parse_prop(
+                            name,
+                            value,
+                            len,
+                            ctx_.cells(),
+                            &mut cells,
+                            interrupt_cells,
+                            self,
+                        );
+                        // 子节点的地址按此转换
+                        bus.absorb(&prop);
+                        match f(ctx_, DtbObj::Property(prop)) {
                             StepInto | StepOver => {}
                             StepOut => ctx = None,
-                            Terminate => return false,
+                            Terminate => return Ok(false),
                         };
                     }
-                    self.tail = tail;
                 }
-                // 跳过
-                Some((&Blk::NOP, tail)) => self.tail = tail,
-                Some((_, _)) | None => unreachable!(),
+                // 结构块在节点内结束
+                Token::Finish => return Err(StructureError::Unbalanced { offset }),
+            }
+        }
+    }
+}
+
+/// 按父节点的 `parent` 解析属性。`rest` 是当前节点剩余的部分。
+///
+/// `#address-cells` 等属性同时更新当前节点的 `cells`。
+/// `interrupt_cells` 是当前节点的中断父节点的 `#interrupt-cells`，用于切分 `interrupts`。
+pub(crate) fn parse_prop<'a>(
+    name: &'a [u8],
+    value: &'a [Blk],
+    len: usize,
+    parent: Cells,
+    cells: &mut Cells,
+    interrupt_cells: Option<u32>,
+    rest: &Walker<'a>,
+) -> Property<'a> {
+    cells.absorb(name, value, len);
+    match name {
+        b"reg" if value.len().is_multiple_of(parent.reg_size()) => Property::Reg(Reg {
+            buf: value,
+            cfg: RegCfg {
+                address_cells: parent.address,
+                size_cells: parent.size,
+            },
+        }),
+        b"ranges" | b"dma-ranges" => {
+            let own = rest.lookahead_cells(*cells);
+            let cfg = RangesCfg {
+                child_address_cells: own.address,
+                parent_address_cells: parent.address,
+                size_cells: own.size,
+            };
+            match Ranges::new(value, cfg) {
+                Some(ranges) if name == b"ranges" => Property::Ranges(ranges),
+                Some(ranges) => Property::DmaRanges(ranges),
+                None => Property::new(name, value, len),
+            }
+        }
+        b"interrupts" if len == value.len() * Blk::LEN => {
+            match interrupt_cells.and_then(|cells| Interrupts::new(value, cells)) {
+                Some(interrupts) => Property::Interrupts(interrupts),
+                None => Property::new(name, value, len),
             }
         }
+        name => Property::new(name, value, len),
     }
 }
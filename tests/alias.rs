@@ -0,0 +1,69 @@
+mod common;
+
+use common::{strs, Builder};
+use dtb_walker::Str;
+
+#[test]
+fn aliases() {
+    let blob = Builder::default()
+        .begin("")
+        .begin("aliases")
+        .prop("serial0", &strs(&["/soc/uart@10000000"]))
+        .prop("i2c10", &strs(&["/soc/i2c"]))
+        .prop("ethernet", &strs(&["/soc/eth"]))
+        .prop("spi99999999999", &strs(&["/soc"]))
+        // 不是绝对路径或没有 `\0` 结尾的值不是别名
+        .prop("relative", &strs(&["soc/uart@10000000"]))
+        .prop("unterminated", b"/soc")
+        .end()
+        .begin("soc")
+        .begin("uart@10000000")
+        .end()
+        .begin("i2c")
+        .begin("rtc@50")
+        .end()
+        .end()
+        .end()
+        .end()
+        .build();
+    let dtb = blob.dtb();
+    let aliases = dtb
+        .aliases()
+        .map(|a| {
+            let a = a.unwrap();
+            let s = |s: Str| s.as_str().unwrap().to_string();
+            (s(a.name), s(a.path), s(a.stem), a.id)
+        })
+        .collect::<Vec<_>>();
+    let expected = [
+        ("serial0", "/soc/uart@10000000", "serial", Some(0)),
+        ("i2c10", "/soc/i2c", "i2c", Some(10)),
+        ("ethernet", "/soc/eth", "ethernet", None),
+        // 数字后缀超出 `u32` 范围
+        ("spi99999999999", "/soc", "spi", None),
+    ]
+    .map(|(name, path, stem, id)| (name.into(), path.into(), stem.into(), id));
+    assert_eq!(aliases, expected);
+
+    let find = |path: &str| {
+        dtb.find_node(path)
+            .unwrap()
+            .map(|node| node.name().as_str().unwrap().to_string())
+    };
+    assert_eq!(find("serial0").as_deref(), Some("uart@10000000"));
+    // `:` 之后的选项被忽略
+    assert_eq!(find("serial0:115200n8").as_deref(), Some("uart@10000000"));
+    assert_eq!(
+        find("/soc/uart@10000000:115200").as_deref(),
+        Some("uart@10000000")
+    );
+    assert_eq!(find("/soc/uart:115200").as_deref(), Some("uart@10000000"));
+    // 别名之后的相对路径
+    assert_eq!(find("i2c10/rtc@50").as_deref(), Some("rtc@50"));
+    assert_eq!(find("i2c10/rtc:x").as_deref(), Some("rtc@50"));
+    // 不存在的别名或别名指向不存在的节点
+    assert_eq!(find("serial"), None);
+    assert_eq!(find("serial1"), None);
+    assert_eq!(find("ethernet"), None);
+    assert_eq!(find("relative"), None);
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use common::{cells, Builder};
+use dtb_walker::Status;
+
+#[test]
+fn chosen() {
+    let blob = Builder::default()
+        .begin("")
+        .begin("chosen")
+        .prop("bootargs", b"console=ttyS0\0quiet\0")
+        .prop("stdout-path", b"/soc/uart@10000000:115200\0")
+        .prop("linux,initrd-start", &cells(&[0x8000_0000]))
+        .prop("linux,initrd-end", &cells(&[0, 0x8100_0000]))
+        .prop("kaslr-seed", &cells(&[1, 2, 3]))
+        .end()
+        .begin("dev")
+        .prop("status", b"okay\0\0")
+        .end()
+        .end()
+        .build();
+    let dtb = blob.dtb();
+    let chosen = dtb.chosen().unwrap().unwrap();
+    // 字符串中间不能有 `\0`
+    assert!(chosen.bootargs.is_none());
+    assert_eq!(
+        chosen.stdout_path.unwrap().as_bytes(),
+        b"/soc/uart@10000000"
+    );
+    assert_eq!(chosen.stdout_options.unwrap().as_bytes(), b"115200");
+    assert_eq!(chosen.initrd_start, Some(0x8000_0000));
+    assert_eq!(chosen.initrd_end, Some(0x8100_0000));
+    assert_eq!(chosen.kaslr_seed, None);
+    let dev = dtb.find_node("/dev").unwrap().unwrap();
+    assert!(!dev.status().unwrap().is_available());
+    assert!(matches!(dev.status(), Ok(Status::Unknown(_))));
+}
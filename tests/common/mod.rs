@@ -0,0 +1,160 @@
+//! 测试用的设备树构造工具。
+
+#![allow(dead_code)]
+
+use dtb_walker::{Dtb, DtbObj, WalkOperation};
+use std::fmt::Write;
+
+/// 8 字节对齐的设备树二进制对象。
+pub struct Blob {
+    buf: Vec<u64>,
+    len: usize,
+}
+
+impl Blob {
+    /// 复制字节序列到对齐的缓冲区。
+    pub fn new(bytes: &[u8]) -> Self {
+        let mut buf = vec![0u64; bytes.len().div_ceil(8)];
+        unsafe { std::slice::from_raw_parts_mut(buf.as_mut_ptr().cast::<u8>(), bytes.len()) }
+            .copy_from_slice(bytes);
+        Self {
+            buf,
+            len: bytes.len(),
+        }
+    }
+
+    /// 读取文件。
+    pub fn load(path: &str) -> Self {
+        Self::new(&std::fs::read(path).unwrap())
+    }
+
+    /// 字节序列。
+    pub fn bytes(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.buf.as_ptr().cast(), self.len) }
+    }
+
+    /// 构造设备树，接受 qemu 生成的设备树中不规范的首部字段。
+    pub fn dtb(&self) -> Dtb<'_> {
+        Dtb::from_slice_filtered(self.bytes(), |_| true)
+            .ok()
+            .expect("invalid header")
+    }
+}
+
+/// 编码 cell 序列。
+pub fn cells(values: &[u32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_be_bytes()).collect()
+}
+
+/// 编码字符串列表。
+pub fn strs(values: &[&str]) -> Vec<u8> {
+    values.iter().flat_map(|s| s.bytes().chain([0])).collect()
+}
+
+/// 按顺序写入标记的结构块构造器，与 dtc 的输出格式相同。
+#[derive(Default)]
+pub struct Builder {
+    structure: Vec<u8>,
+    strings: Vec<u8>,
+    reserved: Vec<(u64, u64)>,
+}
+
+impl Builder {
+    /// 打开节点。
+    pub fn begin(&mut self, name: &str) -> &mut Self {
+        self.structure.extend(1u32.to_be_bytes());
+        self.structure.extend(name.bytes().chain([0]));
+        self.pad();
+        self
+    }
+
+    /// 写入属性。
+    pub fn prop(&mut self, name: &str, value: &[u8]) -> &mut Self {
+        let nameoff = self.name_offset(name);
+        self.structure.extend(3u32.to_be_bytes());
+        self.structure.extend((value.len() as u32).to_be_bytes());
+        self.structure.extend(nameoff.to_be_bytes());
+        self.structure.extend(value);
+        self.pad();
+        self
+    }
+
+    /// 关闭节点。
+    pub fn end(&mut self) -> &mut Self {
+        self.structure.extend(2u32.to_be_bytes());
+        self
+    }
+
+    /// 增加地址保护区中的一项。
+    pub fn reserve(&mut self, address: u64, size: u64) -> &mut Self {
+        self.reserved.push((address, size));
+        self
+    }
+
+    /// 写入结束标记，生成 v17 设备树。
+    pub fn build(&self) -> Blob {
+        let mut structure = self.structure.clone();
+        structure.extend(9u32.to_be_bytes());
+        let mut rsvmap = Vec::new();
+        for (address, size) in self.reserved.iter().chain([&(0, 0)]) {
+            rsvmap.extend(address.to_be_bytes());
+            rsvmap.extend(size.to_be_bytes());
+        }
+        let off_rsvmap = 40;
+        let off_struct = off_rsvmap + rsvmap.len();
+        let off_strings = off_struct + structure.len();
+        let total = off_strings + self.strings.len();
+        let mut bytes = Vec::with_capacity(total);
+        for field in [
+            0xd00dfeed,
+            total,
+            off_struct,
+            off_strings,
+            off_rsvmap,
+            17,
+            16,
+            0,
+            self.strings.len(),
+            structure.len(),
+        ] {
+            bytes.extend((field as u32).to_be_bytes());
+        }
+        bytes.extend(rsvmap);
+        bytes.extend(structure);
+        bytes.extend(&self.strings);
+        Blob::new(&bytes)
+    }
+
+    fn name_offset(&mut self, name: &str) -> u32 {
+        let mut offset = 0;
+        for s in self.strings.split(|c| *c == 0) {
+            if s == name.as_bytes() {
+                return offset as u32;
+            }
+            offset += s.len() + 1;
+        }
+        let offset = self.strings.len();
+        self.strings.extend(name.bytes().chain([0]));
+        offset as u32
+    }
+
+    fn pad(&mut self) {
+        while !self.structure.len().is_multiple_of(4) {
+            self.structure.push(0);
+        }
+    }
+}
+
+/// 以缩进文本记录完整的遍历结果。
+pub fn dump(dtb: &Dtb) -> String {
+    let mut out = String::new();
+    dtb.walk(|ctx, obj| {
+        match obj {
+            DtbObj::SubNode { name } => writeln!(out, "{}{ctx}/{name}", ctx.level()),
+            DtbObj::Property(prop) => writeln!(out, "{} {prop:?}", ctx.level()),
+        }
+        .unwrap();
+        WalkOperation::StepInto
+    });
+    out
+}
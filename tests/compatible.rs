@@ -0,0 +1,60 @@
+mod common;
+
+use common::{strs, Builder};
+
+#[test]
+fn available() {
+    let blob = Builder::default()
+        .begin("")
+        .begin("soc")
+        .begin("uart@0")
+        .prop("compatible", &strs(&["vendor,uart", "ns16550a"]))
+        .end()
+        .begin("uart@1")
+        .prop("compatible", &strs(&["ns16550a"]))
+        .prop("status", &strs(&["disabled"]))
+        .end()
+        .begin("bus")
+        .prop("status", &strs(&["fail"]))
+        .begin("uart@2")
+        .prop("compatible", &strs(&["ns16550a"]))
+        .end()
+        .end()
+        .begin("uart@3")
+        .prop("status", &strs(&["ok"]))
+        .prop("compatible", &strs(&["ns16550a"]))
+        .end()
+        .end()
+        .end()
+        .build();
+    let dtb = blob.dtb();
+    let find = |available: bool| {
+        let found = dtb.find_compatible(&["snps,uart", "ns16550a"]);
+        let found = if available { found.available() } else { found };
+        found
+            .map(|m| {
+                let m = m.unwrap();
+                (
+                    m.node.name().as_str().unwrap().to_string(),
+                    m.index,
+                    m.query,
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    let expected = |names: &[&str]| {
+        names
+            .iter()
+            .map(|&name| {
+                let index = if name == "uart@0" { 1 } else { 0 };
+                (name.to_string(), index, 1)
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        find(false),
+        expected(&["uart@0", "uart@1", "uart@2", "uart@3"])
+    );
+    // 不可用的节点及其整个子树都被跳过，`status` 可以在 `compatible` 之后
+    assert_eq!(find(true), expected(&["uart@0", "uart@3"]));
+}
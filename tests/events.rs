@@ -0,0 +1,63 @@
+mod common;
+
+use common::{cells, Builder};
+use dtb_walker::{Event, Events};
+
+/// 把事件记为字符串，遇到名字在 `skip` 中的节点时跳过其子树。
+fn trace(mut events: Events, skip: &[&str]) -> Vec<String> {
+    let mut ans = Vec::new();
+    while let Some(event) = events.next() {
+        match event.unwrap() {
+            Event::BeginNode { name, depth } => {
+                let name = name.as_str().unwrap();
+                ans.push(format!("{depth}:{name}"));
+                if skip.contains(&name) {
+                    events.skip_subtree();
+                }
+            }
+            Event::Property(_) => ans.push("p".into()),
+            Event::EndNode => ans.push("end".into()),
+        }
+    }
+    ans
+}
+
+#[test]
+fn skip_subtree() {
+    let blob = Builder::default()
+        .begin("")
+        .begin("a")
+        .prop("reg", &cells(&[1]))
+        .begin("b")
+        .begin("c")
+        .prop("reg", &cells(&[2]))
+        .end()
+        .end()
+        .prop("x", &[])
+        .end()
+        .begin("d")
+        .prop("reg", &cells(&[3]))
+        .end()
+        .end()
+        .build();
+    let dtb = blob.dtb();
+    assert_eq!(
+        trace(dtb.events(), &[]),
+        ["0:", "1:a", "p", "2:b", "3:c", "p", "end", "end", "p", "end", "1:d", "p", "end", "end"]
+    );
+    // 跳过之后下一个事件是同一个节点的结束
+    assert_eq!(
+        trace(dtb.events(), &["a"]),
+        ["0:", "1:a", "end", "1:d", "p", "end", "end"]
+    );
+    assert_eq!(
+        trace(dtb.events(), &["b", "d"]),
+        ["0:", "1:a", "p", "2:b", "end", "p", "end", "1:d", "end", "end"]
+    );
+    // 跳过根节点
+    assert_eq!(trace(dtb.events(), &[""]), ["0:", "end"]);
+    // 没有打开的节点时什么也不做
+    let mut events = dtb.events();
+    events.skip_subtree();
+    assert_eq!(trace(events, &[]).len(), 14);
+}
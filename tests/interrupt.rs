@@ -0,0 +1,269 @@
+mod common;
+
+use common::{cells, Blob, Builder};
+use dtb_walker::{DtbObj, Event, InterruptError, PHandle, Property, SpecifierError, WalkOperation};
+
+const QEMU_VIRT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/qemu-virt.dtb");
+
+#[test]
+fn interrupts() {
+    let blob = Blob::load(QEMU_VIRT);
+    let dtb = blob.dtb();
+    let uart = dtb.find_node("/soc/uart@10000000").unwrap().unwrap();
+    let (parent, interrupts) = uart.interrupts().unwrap().unwrap();
+    assert_eq!(parent.name().as_bytes(), b"plic@c000000");
+    assert_eq!(interrupts.cells(), 1);
+    let specifiers = interrupts.map(|s| s.iter().collect::<Vec<_>>());
+    assert_eq!(specifiers.collect::<Vec<_>>(), [[0xa]]);
+    // 没有 `interrupts` 的节点
+    let plic = dtb.find_node("/soc/plic@c000000").unwrap().unwrap();
+    assert!(plic.interrupts().unwrap().is_none());
+    // 遍历时按中断父节点切分，qemu 的 `interrupt-parent` 在 `interrupts` 之后
+    let mut walked = Vec::new();
+    dtb.walk(|ctx, obj| {
+        if let DtbObj::Property(Property::Interrupts(interrupts)) = obj {
+            walked.push((ctx.to_string(), format!("{interrupts:?}")));
+        }
+        WalkOperation::StepInto
+    });
+    assert!(walked.contains(&("/soc/uart@10000000".into(), "[<0xa>]".into())));
+    assert_eq!(walked.len(), 10);
+    // 事件和节点的属性迭代器得到相同的结果
+    let events = dtb
+        .events()
+        .filter(|e| matches!(e, Ok(Event::Property(Property::Interrupts(_)))))
+        .count();
+    assert_eq!(events, walked.len());
+    assert!(uart
+        .properties()
+        .any(|p| matches!(p, Ok(Property::Interrupts(i)) if i.cells() == 1)));
+}
+
+#[test]
+fn interrupts_inherited() {
+    // 根节点的 `interrupt-parent` 被子孙继承，有 `#interrupt-cells` 的父节点优先
+    let blob = Builder::default()
+        .begin("")
+        .prop("interrupts", &cells(&[1, 2]))
+        .prop("interrupt-parent", &cells(&[1]))
+        .begin("intc")
+        .prop("phandle", &cells(&[1]))
+        .prop("interrupt-controller", &[])
+        .prop("#interrupt-cells", &cells(&[2]))
+        .end()
+        .begin("bus")
+        .begin("dev")
+        .prop("interrupts", &cells(&[1, 2, 3, 4]))
+        .end()
+        .end()
+        .begin("gpio")
+        .prop("phandle", &cells(&[2]))
+        .prop("#interrupt-cells", &cells(&[1]))
+        .begin("key")
+        .prop("interrupts", &cells(&[1, 2, 3]))
+        .end()
+        .begin("led")
+        .prop("interrupts", &cells(&[1, 2]))
+        .prop("interrupt-parent", &cells(&[1]))
+        .end()
+        .begin("dangling")
+        .prop("interrupts", &cells(&[1]))
+        .prop("interrupt-parent", &cells(&[7]))
+        .end()
+        .end()
+        .end()
+        .build();
+    let dtb = blob.dtb();
+    let mut walked = Vec::new();
+    dtb.walk(|ctx, obj| {
+        if let DtbObj::Property(prop) = obj {
+            match prop {
+                Property::Interrupts(i) => walked.push((ctx.to_string(), Some(i.cells()))),
+                Property::General { name, .. } if name.as_bytes() == b"interrupts" => {
+                    walked.push((ctx.to_string(), None))
+                }
+                _ => {}
+            }
+        }
+        WalkOperation::StepInto
+    });
+    let expected = [
+        ("", Some(2)),
+        ("/bus/dev", Some(2)),
+        ("/gpio/key", Some(1)),
+        ("/gpio/led", Some(2)),
+        ("/gpio/dangling", None),
+    ]
+    .map(|(path, cells)| (path.to_string(), cells));
+    assert_eq!(walked, expected);
+    // 节点的上下文与遍历时相同
+    for (path, cells) in &expected[1..] {
+        let node = dtb.find_node(path).unwrap().unwrap();
+        let found = node.properties().find_map(|p| match p.unwrap() {
+            Property::Interrupts(i) => Some(Some(i.cells())),
+            Property::General { name, .. } if name.as_bytes() == b"interrupts" => Some(None),
+            _ => None,
+        });
+        assert_eq!(found, Some(*cells), "{path}");
+    }
+}
+
+#[test]
+fn interrupts_invalid() {
+    let blob = Builder::default()
+        .begin("")
+        .begin("intc")
+        .prop("phandle", &cells(&[1]))
+        .prop("#interrupt-cells", &cells(&[2]))
+        .end()
+        .begin("ok")
+        .prop("interrupt-parent", &cells(&[1]))
+        .prop("interrupts", &cells(&[1, 2, 3, 4]))
+        .end()
+        .begin("odd")
+        .prop("interrupt-parent", &cells(&[1]))
+        .prop("interrupts", &cells(&[1, 2, 3]))
+        .end()
+        .begin("orphan")
+        .prop("interrupts", &cells(&[1]))
+        .end()
+        .begin("dangling")
+        .prop("interrupt-parent", &cells(&[7]))
+        .prop("interrupts", &cells(&[1]))
+        .end()
+        .end()
+        .build();
+    let dtb = blob.dtb();
+    let node = |path| dtb.find_node(path).unwrap().unwrap();
+    let (parent, interrupts) = node("/ok").interrupts().unwrap().unwrap();
+    assert_eq!(parent.phandle(), Ok(Some(PHandle::from(1))));
+    assert_eq!(interrupts.count(), 2);
+    assert_eq!(
+        node("/odd").interrupts().err(),
+        Some(InterruptError::InvalidLength)
+    );
+    assert_eq!(
+        node("/orphan").interrupts().err(),
+        Some(InterruptError::NoParent)
+    );
+    assert_eq!(
+        node("/dangling").interrupts().err(),
+        Some(InterruptError::NoParent)
+    );
+}
+
+#[test]
+fn map_interrupt_pci() {
+    let blob = Blob::load(QEMU_VIRT);
+    let dtb = blob.dtb();
+    let pci = dtb.find_node("/soc/pci@30000000").unwrap().unwrap();
+    let mut buf = [0; 4];
+    let (controller, specifier) = pci
+        .map_interrupt(&[0x800, 0, 0], &[1], &mut buf)
+        .unwrap()
+        .unwrap();
+    assert_eq!(controller.name().as_bytes(), b"plic@c000000");
+    assert_eq!(specifier, [0x21]);
+    // 掩码之外的位不影响匹配
+    let (_, specifier) = pci
+        .map_interrupt(&[0x0200_0800, 0, 0x1234], &[1], &mut buf)
+        .unwrap()
+        .unwrap();
+    assert_eq!(specifier, [0x21]);
+}
+
+#[test]
+fn map_interrupt_chained() {
+    // 第二级联结点没有 `#address-cells`，地址宽度沿用第一级映射项中的 0
+    let blob = Builder::default()
+        .begin("")
+        .begin("intc")
+        .prop("phandle", &cells(&[1]))
+        .prop("interrupt-controller", &[])
+        .prop("#interrupt-cells", &cells(&[1]))
+        .end()
+        .begin("nexus")
+        .prop("phandle", &cells(&[2]))
+        .prop("#interrupt-cells", &cells(&[1]))
+        .prop("interrupt-map", &cells(&[3, 1, 0x30]))
+        .end()
+        .begin("bus")
+        .prop("#address-cells", &cells(&[1]))
+        .prop("#interrupt-cells", &cells(&[1]))
+        .prop("interrupt-map", &cells(&[0x10, 5, 2, 3]))
+        .end()
+        .end()
+        .build();
+    let dtb = blob.dtb();
+    let bus = dtb.find_node("/bus").unwrap().unwrap();
+    let mut buf = [0; 1];
+    let (controller, specifier) = bus.map_interrupt(&[0x10], &[5], &mut buf).unwrap().unwrap();
+    assert_eq!(controller.name().as_bytes(), b"intc");
+    assert_eq!(specifier, [0x30]);
+    assert!(bus
+        .map_interrupt(&[0x10], &[6], &mut buf)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn interrupts_extended() {
+    let blob = Blob::load(QEMU_VIRT);
+    let dtb = blob.dtb();
+    // 每个 hart 的 M 态和 S 态中断，各 hart 的本地中断控制器 `#interrupt-cells = <1>`
+    let harts = [8, 6, 4, 2];
+    for (path, m, s) in [
+        ("/soc/plic@c000000", 0xb, 0x9),
+        ("/soc/clint@2000000", 0x3, 0x7),
+    ] {
+        let node = dtb.find_node(path).unwrap().unwrap();
+        let entries = node
+            .interrupts_extended()
+            .map(|e| {
+                let (phandle, specifier) = e.unwrap();
+                (phandle, specifier.iter().collect::<Vec<_>>())
+            })
+            .collect::<Vec<_>>();
+        let expected = harts
+            .iter()
+            .flat_map(|&p| [(PHandle::from(p), vec![m]), (PHandle::from(p), vec![s])])
+            .collect::<Vec<_>>();
+        assert_eq!(entries, expected, "{path}");
+    }
+    let intc = dtb.node_by_phandle(PHandle::from(8)).unwrap().unwrap();
+    assert_eq!(intc.name().as_bytes(), b"interrupt-controller");
+    assert!(intc
+        .properties()
+        .any(|p| matches!(p, Ok(Property::InterruptCells(1)))));
+    // 引用号 0 不是占位符
+    let blob = Builder::default()
+        .begin("")
+        .begin("intc")
+        .prop("phandle", &cells(&[1]))
+        .prop("#interrupt-cells", &cells(&[1]))
+        .end()
+        .begin("dev")
+        .prop("interrupts-extended", &cells(&[1, 5, 0, 1, 6]))
+        .end()
+        .end()
+        .build();
+    let dtb = blob.dtb();
+    let dev = dtb.find_node("/dev").unwrap().unwrap();
+    let entries = dev
+        .interrupts_extended()
+        .map(|e| e.map(|(phandle, specifier)| (phandle, specifier.iter().collect::<Vec<_>>())))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        entries,
+        [
+            Ok((PHandle::from(1), vec![5])),
+            Err(SpecifierError::Dangling(PHandle::from(0))),
+        ]
+    );
+    // 其他属性中引用号 0 是占位符
+    let placeholders = dev
+        .phandle_args("interrupts-extended", "#interrupt-cells")
+        .map(|e| e.unwrap().0)
+        .collect::<Vec<_>>();
+    assert_eq!(placeholders, [1, 0, 1].map(PHandle::from));
+}
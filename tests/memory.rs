@@ -0,0 +1,158 @@
+﻿mod common;
+
+use common::{cells, strs, Blob, Builder};
+use dtb_walker::{MemRegion, MemoryMapError};
+
+/// 构造 `#address-cells` 和 `#size-cells` 都为 1 的设备树，`memory` 中每两个 cell 是一项。
+fn tree(memory: &[u32], f: impl FnOnce(&mut Builder)) -> Blob {
+    let mut builder = Builder::default();
+    builder
+        .begin("")
+        .prop("#address-cells", &cells(&[1]))
+        .prop("#size-cells", &cells(&[1]))
+        .begin("memory@0")
+        .prop("device_type", &strs(&["memory"]))
+        .prop("reg", &cells(memory))
+        .end();
+    f(&mut builder);
+    builder.end().build()
+}
+
+fn region(start: u64, end: u64) -> MemRegion {
+    MemRegion { start, end }
+}
+
+fn build(
+    blob: &Blob,
+    align: u64,
+    usable: usize,
+    reserved: usize,
+) -> Result<(Vec<MemRegion>, Vec<MemRegion>), MemoryMapError> {
+    let mut u = vec![MemRegion::default(); usable];
+    let mut r = vec![MemRegion::default(); reserved];
+    let dtb = blob.dtb();
+    let map = dtb.memory_map().align(align).build(&mut u, &mut r)?;
+    Ok((map.usable.to_vec(), map.reserved.to_vec()))
+}
+
+#[test]
+fn merge_adjacent() {
+    let blob = tree(&[0x1000, 0x1000, 0, 0x1000, 0x3000, 0x1000], |_| {});
+    let (usable, _) = build(&blob, 1, 2, 0).unwrap();
+    assert_eq!(usable, [region(0, 0x2000), region(0x3000, 0x4000)]);
+}
+
+#[test]
+fn merge_spanning() {
+    // 最后一项覆盖前三项
+    let blob = tree(
+        &[0, 0x1000, 0x2000, 0x1000, 0x4000, 0x1000, 0x800, 0x4000],
+        |_| {},
+    );
+    let (usable, _) = build(&blob, 1, 3, 0).unwrap();
+    assert_eq!(usable, [region(0, 0x5000)]);
+}
+
+#[test]
+fn remove_spanning() {
+    let blob = tree(&[0, 0x1000, 0x2000, 0x1000, 0x4000, 0x1000], |b| {
+        b.reserve(0x800, 0x4000);
+    });
+    let (usable, reserved) = build(&blob, 1, 3, 1).unwrap();
+    assert_eq!(usable, [region(0, 0x800), region(0x4800, 0x5000)]);
+    assert_eq!(reserved, [region(0x800, 0x4800)]);
+}
+
+#[test]
+fn overflow() {
+    // 扣除保留区间把一个区间拆成两个
+    let blob = tree(&[0, 0x10000], |b| {
+        b.reserve(0x1000, 0x1000);
+    });
+    assert_eq!(
+        build(&blob, 1, 2, 1).unwrap().0,
+        [region(0, 0x1000), region(0x2000, 0x10000)]
+    );
+    assert_eq!(build(&blob, 1, 1, 1), Err(MemoryMapError::UsableOverflow));
+    assert_eq!(build(&blob, 1, 2, 0), Err(MemoryMapError::ReservedOverflow));
+    // 不相邻的区间
+    let blob = tree(&[0, 0x1000, 0x2000, 0x1000], |_| {});
+    assert_eq!(build(&blob, 1, 1, 0), Err(MemoryMapError::UsableOverflow));
+}
+
+#[test]
+fn align() {
+    // 第二个区间向内对齐后为空
+    let blob = tree(&[0, 0x1800, 0x2800, 0x800], |b| {
+        b.reserve(0x1100, 0x100);
+    });
+    let (usable, reserved) = build(&blob, 0x1000, 2, 1).unwrap();
+    assert_eq!(usable, [region(0, 0x1000)]);
+    assert_eq!(reserved, [region(0x1000, 0x2000)]);
+}
+
+#[test]
+fn reserved_memory() {
+    let blob = tree(&[0, 0x10000], |b| {
+        b.begin("reserved-memory")
+            .prop("#address-cells", &cells(&[1]))
+            .prop("#size-cells", &cells(&[1]))
+            .prop("ranges", &[])
+            .begin("no-map@1000")
+            .prop("reg", &cells(&[0x1000, 0x1000]))
+            .prop("no-map", &[])
+            .end()
+            .begin("static@4000")
+            .prop("reg", &cells(&[0x4000, 0x1000]))
+            .end()
+            .begin("reusable@8000")
+            .prop("reg", &cells(&[0x8000, 0x1000]))
+            .prop("reusable", &[])
+            .end()
+            .begin("disabled@c000")
+            .prop("reg", &cells(&[0xc000, 0x1000]))
+            .prop("status", &strs(&["disabled"]))
+            .end()
+            .begin("dynamic")
+            .prop("size", &cells(&[0x1000]))
+            .end()
+            .end();
+    });
+    let dtb = blob.dtb();
+    let available = dtb
+        .reserved_memory()
+        .map(|region| region.unwrap().status.is_available())
+        .collect::<Vec<_>>();
+    assert_eq!(available, [true, true, true, false, true]);
+    let (usable, reserved) = build(&blob, 1, 4, 4).unwrap();
+    assert_eq!(
+        reserved,
+        [
+            region(0x1000, 0x2000),
+            region(0x4000, 0x5000),
+            region(0x8000, 0x9000),
+        ]
+    );
+    assert_eq!(
+        usable,
+        [
+            region(0, 0x1000),
+            region(0x2000, 0x4000),
+            region(0x5000, 0x10000),
+        ]
+    );
+}
+
+#[test]
+fn reserve_empty() {
+    // 长度为 0 的保留项不拆分可用区间
+    let blob = tree(&[0, 0x10000], |b| {
+        b.reserve(0x8000, 0).reserve(0x8100, 0);
+    });
+    let (usable, reserved) = build(&blob, 1, 1, 0).unwrap();
+    assert_eq!(usable, [region(0, 0x10000)]);
+    assert!(reserved.is_empty());
+    let (usable, reserved) = build(&blob, 0x1000, 1, 0).unwrap();
+    assert_eq!(usable, [region(0, 0x10000)]);
+    assert!(reserved.is_empty());
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use common::{cells, Builder};
+use dtb_walker::{NodeName, NodeNameError, Str};
+
+#[test]
+fn node_name() {
+    let name = NodeName::new(Str::from("pci@1,0"));
+    assert_eq!(name.base().as_bytes(), b"pci");
+    let unit = name.unit_address().unwrap();
+    assert_eq!(unit.as_str().as_bytes(), b"1,0");
+    assert_eq!(unit.clone().collect::<Vec<_>>(), [Some(1), Some(0)]);
+    assert_eq!(unit.to_u64(), None);
+    assert_eq!(name.to_string(), "pci@1,0");
+    assert_eq!(name.validate(), Ok(()));
+    // 根节点
+    assert_eq!(NodeName::new(Str::from("")).validate(), Ok(()));
+    // 不合规的节点名
+    for (name, err) in [
+        ("@10", NodeNameError::Empty),
+        ("a234567890123456789012345678901x", NodeNameError::TooLong),
+        ("1wire", NodeNameError::InvalidStart(b'1')),
+        ("uart#0", NodeNameError::InvalidChar(b'#')),
+        ("uart@", NodeNameError::EmptyUnitAddress),
+        ("uart@1 0", NodeNameError::InvalidChar(b' ')),
+    ] {
+        assert_eq!(
+            NodeName::new(Str::from(name)).validate(),
+            Err(err),
+            "{name}"
+        );
+    }
+    // 本体最多 31 个字符，不包括单元地址
+    let name = NodeName::new(Str::from("a234567890123456789012345678901@ffff"));
+    assert_eq!(name.validate(), Ok(()));
+    assert_eq!(name.unit_address().unwrap().to_u64(), Some(0xffff));
+}
+
+#[test]
+fn validate_name() {
+    let blob = Builder::default()
+        .begin("")
+        .prop("#address-cells", &cells(&[1]))
+        .prop("#size-cells", &cells(&[1]))
+        .begin("pci@30000000")
+        .prop("reg", &cells(&[0x3000_0000, 0x1000]))
+        .prop("#address-cells", &cells(&[3]))
+        .prop("#size-cells", &cells(&[2]))
+        // 设备号 1，功能号 0
+        .begin("dev@1,0")
+        .prop("reg", &cells(&[0x0800, 0, 0, 0, 0]))
+        .end()
+        .begin("dev@1")
+        .prop("reg", &cells(&[0x0800, 0, 0, 0, 0]))
+        .end()
+        .begin("dev@1,2")
+        .prop("reg", &cells(&[0x0a00, 0, 0, 0, 0]))
+        .end()
+        .begin("dev@2,0")
+        .prop("reg", &cells(&[0x0800, 0, 0, 0, 0]))
+        .end()
+        .end()
+        .begin("i2c@10001000")
+        .prop("reg", &cells(&[0x1000_1000, 0x100]))
+        .prop("#address-cells", &cells(&[1]))
+        .prop("#size-cells", &cells(&[0]))
+        .begin("rtc@50")
+        .prop("reg", &cells(&[0x50]))
+        .end()
+        .begin("eeprom@51")
+        .prop("reg", &cells(&[0x50]))
+        .end()
+        .begin("sensor")
+        .prop("reg", &cells(&[0x52]))
+        .end()
+        .begin("mux@53")
+        .end()
+        .end()
+        .end()
+        .build();
+    let dtb = blob.dtb();
+    for (path, expected) in [
+        ("/", Ok(())),
+        ("/pci@30000000", Ok(())),
+        ("/pci@30000000/dev@1,0", Ok(())),
+        ("/pci@30000000/dev@1", Ok(())),
+        ("/pci@30000000/dev@1,2", Ok(())),
+        (
+            "/pci@30000000/dev@2,0",
+            Err(NodeNameError::UnitAddressMismatch),
+        ),
+        ("/i2c@10001000", Ok(())),
+        ("/i2c@10001000/rtc@50", Ok(())),
+        (
+            "/i2c@10001000/eeprom@51",
+            Err(NodeNameError::UnitAddressMismatch),
+        ),
+        (
+            "/i2c@10001000/sensor",
+            Err(NodeNameError::MissingUnitAddress),
+        ),
+        (
+            "/i2c@10001000/mux@53",
+            Err(NodeNameError::UnexpectedUnitAddress),
+        ),
+    ] {
+        let node = dtb.find_node(path).unwrap().unwrap();
+        assert_eq!(node.validate_name(), expected, "{path}");
+    }
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use common::{cells, strs, Builder};
+use dtb_walker::{Dtb, PHandle, SpecifierError};
+
+fn clocks(clocks: &[u32], names: &[u8]) -> common::Blob {
+    Builder::default()
+        .begin("")
+        .begin("osc")
+        .prop("phandle", &cells(&[1]))
+        .prop("#clock-cells", &cells(&[0]))
+        .end()
+        .begin("pll")
+        .prop("phandle", &cells(&[2]))
+        .prop("#clock-cells", &cells(&[2]))
+        .end()
+        .begin("gpio")
+        .prop("phandle", &cells(&[3]))
+        .end()
+        .begin("dev")
+        .prop("clocks", &cells(clocks))
+        .prop("clock-names", names)
+        .end()
+        .end()
+        .build()
+}
+
+type Entry = Result<(PHandle, Vec<u32>), SpecifierError>;
+
+fn collect(dtb: &Dtb, name: &str) -> Vec<Entry> {
+    dtb.find_node("/dev")
+        .unwrap()
+        .unwrap()
+        .phandle_args(name, "#clock-cells")
+        .map(|e| e.map(|(phandle, args)| (phandle, args.iter().collect())))
+        .collect()
+}
+
+#[test]
+fn phandle_args() {
+    let blob = clocks(&[1, 0, 2, 7, 8], &strs(&["apb", "none", "core"]));
+    let dtb = blob.dtb();
+    assert_eq!(
+        collect(&dtb, "clocks"),
+        [
+            Ok((PHandle::from(1), vec![])),
+            Ok((PHandle::from(0), vec![])),
+            Ok((PHandle::from(2), vec![7, 8])),
+        ]
+    );
+    // 没有这个属性
+    assert!(collect(&dtb, "resets").is_empty());
+    // 引用不存在的节点
+    let blob = clocks(&[1, 9, 2, 7, 8], &[]);
+    assert_eq!(
+        collect(&blob.dtb(), "clocks"),
+        [
+            Ok((PHandle::from(1), vec![])),
+            Err(SpecifierError::Dangling(PHandle::from(9))),
+        ]
+    );
+    // 最后一项缺少参数
+    let blob = clocks(&[1, 2, 7], &[]);
+    assert_eq!(
+        collect(&blob.dtb(), "clocks"),
+        [
+            Ok((PHandle::from(1), vec![])),
+            Err(SpecifierError::Truncated(PHandle::from(2))),
+        ]
+    );
+    // 引用的节点没有 `#clock-cells`
+    let blob = clocks(&[3, 1], &[]);
+    assert_eq!(
+        collect(&blob.dtb(), "clocks"),
+        [Err(SpecifierError::MissingCells(PHandle::from(3)))]
+    );
+}
+
+#[test]
+fn phandle_args_by_name() {
+    let find = |names: &[u8], entry: &str| {
+        let blob = clocks(&[1, 2, 7, 8, 9], names);
+        let dtb = blob.dtb();
+        let dev = dtb.find_node("/dev").unwrap().unwrap();
+        dev.phandle_args_by_name("clocks", "#clock-cells", "clock-names", entry)
+            .map(|e| e.map(|(phandle, args)| (phandle, args.iter().collect::<Vec<_>>())))
+    };
+    let names = strs(&["apb", "core", "dangling"]);
+    assert_eq!(find(&names, "apb"), Ok(Some((PHandle::from(1), vec![]))));
+    assert_eq!(
+        find(&names, "core"),
+        Ok(Some((PHandle::from(2), vec![7, 8])))
+    );
+    assert_eq!(
+        find(&names, "dangling"),
+        Err(SpecifierError::Dangling(PHandle::from(9)))
+    );
+    assert_eq!(find(&names, "bus"), Ok(None));
+    // 结尾的 `\0` 不是一个空名字
+    assert_eq!(find(b"apb\0", ""), Ok(None));
+    // 没有结尾的 `\0`
+    assert_eq!(find(b"apb", "apb"), Err(SpecifierError::InvalidNames));
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use common::{cells, Builder};
+use dtb_walker::{DtbObj, Property, TranslateError, WalkOperation};
+
+#[test]
+fn translate() {
+    let blob = Builder::default()
+        .begin("")
+        .prop("#address-cells", &cells(&[2]))
+        .prop("#size-cells", &cells(&[2]))
+        .begin("soc")
+        .prop("#address-cells", &cells(&[2]))
+        .prop("#size-cells", &cells(&[2]))
+        .prop("ranges", &[])
+        .begin("pci@30000000")
+        .prop("#address-cells", &cells(&[3]))
+        .prop("#size-cells", &cells(&[2]))
+        .prop(
+            "ranges",
+            &cells(&[
+                0x0100_0000,
+                0,
+                0,
+                0,
+                0x0300_0000,
+                0,
+                0x1_0000, // I/O
+                0x0200_0000,
+                0,
+                0x4000_0000,
+                0,
+                0x4000_0000,
+                0,
+                0x4000_0000, // 32 位内存
+                0x0300_0000,
+                4,
+                0,
+                4,
+                0,
+                4,
+                0, // 64 位内存
+            ]),
+        )
+        .begin("dev@1,0")
+        .prop(
+            "reg",
+            &cells(&[
+                0x0000_0800,
+                0,
+                0,
+                0,
+                0, // 配置空间
+                0x0100_0800,
+                0,
+                0x10,
+                0,
+                0x10, // I/O
+                0x0200_0800,
+                0,
+                0x4000_1000,
+                0,
+                0x1000, // 32 位内存
+                0x4300_0800,
+                4,
+                0x10,
+                0,
+                0x10, // 可预取的 64 位内存
+                0x0200_0800,
+                0,
+                0x10,
+                0,
+                0x10, // 不在映射中
+            ]),
+        )
+        .end()
+        .end()
+        .begin("bus@10000000")
+        .prop("#address-cells", &cells(&[1]))
+        .prop("#size-cells", &cells(&[1]))
+        .prop("ranges", &cells(&[0, 0, 0x1000_0000, 0x1000]))
+        .begin("dev@20")
+        .prop("reg", &cells(&[0x20, 4, 0x1000, 4]))
+        .end()
+        .end()
+        .begin("i2c@10001000")
+        .prop("#address-cells", &cells(&[1]))
+        .prop("#size-cells", &cells(&[0]))
+        .begin("rtc@50")
+        .prop("reg", &cells(&[0x50]))
+        .end()
+        .end()
+        .end()
+        .end()
+        .build();
+    let mut translated = Vec::new();
+    blob.dtb().walk(|ctx, obj| {
+        if let DtbObj::Property(Property::Reg(reg)) = obj {
+            translated.extend(reg.map(|entry| ctx.translate(entry.raw_address())));
+        }
+        WalkOperation::StepInto
+    });
+    assert_eq!(
+        translated,
+        [
+            Err(TranslateError::OutOfRange { level: 2 }),
+            Ok(0x300_0010),
+            Ok(0x4000_1000),
+            Ok(0x4_0000_0010),
+            Err(TranslateError::OutOfRange { level: 2 }),
+            Ok(0x1000_0020),
+            Err(TranslateError::OutOfRange { level: 2 }),
+            // I2C 总线没有 `ranges`
+            Err(TranslateError::NoRanges { level: 2 }),
+        ]
+    );
+}
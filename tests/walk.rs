@@ -0,0 +1,169 @@
+mod common;
+
+use common::{cells, Blob, Builder};
+use dtb_walker::{Dtb, Events, MemRegion, StructureError, WalkOperation};
+
+const QEMU_VIRT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/qemu-virt.dtb");
+
+/// 嵌套 `depth` 层的设备树，包括根节点。
+fn nested(depth: usize) -> Blob {
+    let mut builder = Builder::default();
+    builder.begin("");
+    for _ in 1..depth {
+        builder.begin("n").prop("reg", &cells(&[0, 0, 0]));
+    }
+    for _ in 0..depth {
+        builder.end();
+    }
+    builder.build()
+}
+
+/// 所有公开的遍历方式都不应 panic，也不应在不合规的结构块上成功。
+fn walk_all(dtb: &Dtb) -> Result<(), StructureError> {
+    let walk = dtb.try_walk(|_, _| WalkOperation::StepInto);
+    let available = dtb.try_walk_available(|_, _| WalkOperation::StepInto);
+    let events = dtb.events().try_for_each(|e| e.map(|_| ()));
+    assert_eq!(walk, available);
+    assert_eq!(walk, events);
+    for m in dtb.find_compatible(&["riscv"]).flatten() {
+        let _ = m.node.with_context(|ctx| ctx.level());
+    }
+    let _ = dtb.aliases().count();
+    let _ = dtb.chosen();
+    let mut usable = [MemRegion::default(); 8];
+    let mut reserved = [MemRegion::default(); 8];
+    let _ = dtb.memory_map().build(&mut usable, &mut reserved);
+    walk
+}
+
+#[test]
+fn too_deep() {
+    let blob = nested(100_000);
+    let dtb = blob.dtb();
+    assert!(matches!(
+        dtb.try_walk(|_, _| WalkOperation::StepInto),
+        Err(StructureError::TooDeep { .. })
+    ));
+    assert!(matches!(
+        dtb.try_walk_available(|_, _| WalkOperation::StepInto),
+        Err(StructureError::TooDeep { .. })
+    ));
+    // 跳过的子树也受限制
+    assert!(matches!(
+        dtb.try_walk(|_, _| WalkOperation::StepOver),
+        Err(StructureError::TooDeep { .. })
+    ));
+    assert!(matches!(
+        dtb.events().find_map(Result::err),
+        Some(StructureError::TooDeep { .. })
+    ));
+    // 节点可以逐级找到，但构造上下文受限制
+    let mut node = dtb.root().unwrap();
+    for _ in 0..Events::MAX_DEPTH + 8 {
+        node = node.child("n").unwrap().unwrap();
+    }
+    assert!(matches!(
+        node.with_context(|ctx| ctx.level()),
+        Err(StructureError::TooDeep { .. })
+    ));
+}
+
+#[test]
+fn max_depth() {
+    let blob = nested(Events::MAX_DEPTH);
+    let dtb = blob.dtb();
+    let mut nodes = 0;
+    dtb.try_walk(|_, _| {
+        nodes += 1;
+        WalkOperation::StepInto
+    })
+    .unwrap();
+    assert!(nodes > Events::MAX_DEPTH);
+    assert!(dtb.events().all(|e| e.is_ok()));
+    let mut node = dtb.root().unwrap();
+    for _ in 1..Events::MAX_DEPTH {
+        node = node.child("n").unwrap().unwrap();
+    }
+    assert_eq!(
+        node.with_context(|ctx| ctx.level()),
+        Ok(Events::MAX_DEPTH - 1)
+    );
+    assert!(matches!(
+        nested(Events::MAX_DEPTH + 1)
+            .dtb()
+            .try_walk(|_, _| WalkOperation::StepInto),
+        Err(StructureError::TooDeep { .. })
+    ));
+}
+
+#[test]
+fn truncated() {
+    let original = Blob::load(QEMU_VIRT);
+    assert_eq!(walk_all(&original.dtb()), Ok(()));
+    let size_dt_struct = original.dtb().header().size_dt_struct().unwrap();
+    // 截短结构块
+    for size in (0..size_dt_struct).step_by(4) {
+        let mut bytes = original.bytes().to_vec();
+        bytes[36..40].copy_from_slice(&size.to_be_bytes());
+        let blob = Blob::new(&bytes);
+        assert!(walk_all(&blob.dtb()).is_err(), "size_dt_struct = {size}");
+    }
+    // 截短整个设备树
+    let total_size = original.dtb().total_size();
+    for len in (0..total_size).step_by(4) {
+        let blob = Blob::new(&original.bytes()[..len]);
+        assert!(Dtb::from_slice_filtered(blob.bytes(), |_| true).is_err());
+    }
+}
+
+#[test]
+fn corrupted() {
+    let original = Blob::load(QEMU_VIRT);
+    let dtb = original.dtb();
+    let header = dtb.header();
+    let begin = header.off_dt_struct() as usize;
+    let end = begin + header.size_dt_struct().unwrap() as usize;
+    for offset in (begin..end).step_by(4) {
+        for word in [0, 2, 3, 9, u32::MAX] {
+            let mut bytes = original.bytes().to_vec();
+            bytes[offset..offset + 4].copy_from_slice(&word.to_be_bytes());
+            let _ = walk_all(&Blob::new(&bytes).dtb());
+        }
+    }
+}
+
+/// 由 `qemu-virt.dtb` 按 `dtc -V 3` 的格式转换：节点名是完整路径，长度不小于 8 的属性值按 8 字节对齐，
+/// 每个节点补充 `name` 属性，首部只有 36 字节、没有 `size_dt_struct`。
+const QEMU_VIRT_V3: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/qemu-virt-v3.dtb"
+);
+
+#[test]
+fn legacy() {
+    let v17 = Blob::load(QEMU_VIRT);
+    let v17 = v17.dtb();
+    let v3 = Blob::load(QEMU_VIRT_V3);
+    let v3 = Dtb::from_slice(v3.bytes()).ok().expect("invalid header");
+    let header = v3.header();
+    assert_eq!(header.version(), 3);
+    assert_eq!(header.header_len(), 36);
+    assert_eq!(header.size_dt_struct(), None);
+    assert_eq!(header.boot_cpuid_phys(), v17.header().boot_cpuid_phys());
+    assert_eq!(walk_all(&v3), Ok(()));
+    // 除了补充的 `name` 属性，遍历结果与 v17 相同
+    let dump = common::dump(&v3);
+    let (names, dump): (Vec<_>, Vec<_>) = dump.lines().partition(|line| {
+        line.split_once(' ')
+            .is_some_and(|(_, p)| p.starts_with("name = "))
+    });
+    assert_eq!(dump.join("\n") + "\n", common::dump(&v17));
+    // 根节点也有 `name` 属性
+    assert_eq!(
+        names.len(),
+        dump.iter().filter(|l| !l.contains(' ')).count() + 1
+    );
+    assert!(v3.mem_reserve().eq(v17.mem_reserve()));
+    let uart = v3.find_node("/soc/uart@10000000").unwrap().unwrap();
+    assert_eq!(uart.name().as_bytes(), b"uart@10000000");
+}